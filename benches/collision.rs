@@ -0,0 +1,88 @@
+//! Compares the brute-force collision scan with the `SpatialGrid` broadphase.
+//! Run with `cargo bench`.
+
+// The module's tests come along too, but only the test harness runs them
+#[allow(dead_code, unused_imports)]
+#[path = "../src/collision.rs"]
+mod collision;
+
+use std::time::{Duration, Instant};
+
+use collision::{Hitbox, SpatialGrid};
+use macroquad::{math::vec2, rand};
+
+const WIDTH: f32 = 1920.0;
+const HEIGHT: f32 = 1080.0;
+const ITERATIONS: u32 = 20;
+
+fn random_hitboxes(count: usize, size: f32) -> Vec<Hitbox> {
+    (0..count)
+        .map(|i| {
+            let shape = if i % 2 == 0 {
+                Hitbox::circle(0.5)
+            } else {
+                Hitbox::rect(0.8, 0.6)
+            };
+            shape.placed(
+                vec2(rand::gen_range(0.0, WIDTH), rand::gen_range(0.0, HEIGHT)),
+                vec2(size, size),
+            )
+        })
+        .collect()
+}
+
+fn brute_force(enemies: &[Hitbox], projectiles: &[Hitbox]) -> usize {
+    projectiles
+        .iter()
+        .map(|p| enemies.iter().filter(|e| p.collides_with(e)).count())
+        .sum()
+}
+
+fn broadphase(grid: &mut SpatialGrid, enemies: &[Hitbox], projectiles: &[Hitbox]) -> usize {
+    let mut candidates = vec![];
+    grid.clear();
+    for (i, enemy) in enemies.iter().enumerate() {
+        grid.insert(i, enemy.bounds());
+    }
+    projectiles
+        .iter()
+        .map(|p| {
+            grid.query(p.bounds(), &mut candidates);
+            candidates
+                .iter()
+                .filter(|&&i| p.collides_with(&enemies[i]))
+                .count()
+        })
+        .sum()
+}
+
+fn time(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut hits = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        hits = std::hint::black_box(f());
+    }
+    (start.elapsed() / ITERATIONS, hits)
+}
+
+fn main() {
+    rand::srand(0);
+    println!(
+        "{:>8} {:>8} {:>14} {:>14} {:>8}",
+        "enemies", "bullets", "brute force", "grid", "hits"
+    );
+    for count in [100, 500, 1000, 2000, 5000] {
+        let enemies = random_hitboxes(count, 48.0);
+        let projectiles = random_hitboxes(count, 16.0);
+        let mut grid = SpatialGrid::new(64.0);
+
+        let (brute_time, brute_hits) = time(|| brute_force(&enemies, &projectiles));
+        let (grid_time, grid_hits) = time(|| broadphase(&mut grid, &enemies, &projectiles));
+        assert_eq!(brute_hits, grid_hits, "broadphase missed collisions");
+
+        println!(
+            "{:>8} {:>8} {:>14?} {:>14?} {:>8}",
+            count, count, brute_time, grid_time, grid_hits
+        );
+    }
+}
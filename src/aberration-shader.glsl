@@ -0,0 +1,19 @@
+#version 100
+
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform float amount;
+
+void main()
+{
+    // Split the channels outwards from the centre, more towards the edges
+    vec2 offset = (uv - .5) * amount * .03;
+    float r = texture2D(Texture, uv + offset).r;
+    float g = texture2D(Texture, uv).g;
+    float b = texture2D(Texture, uv - offset).b;
+
+    gl_FragColor = vec4(r, g, b, 1.);
+}
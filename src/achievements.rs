@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use nanoserde::{DeRon, SerRon};
+
+use crate::{
+    canvas,
+    events::EventBus,
+    game::Game,
+    persist,
+    resources::Resources,
+    scoring,
+    stats::{LifetimeStats, RunStats},
+};
+
+const DEFINITIONS_PATH: &str = "achievements.ron";
+const UNLOCKS_FILE: &str = "unlocks.ron";
+/// Seconds each unlock notification stays up, including sliding in and out.
+const TOAST_DURATION: f32 = 3.0;
+const TOAST_SLIDE: f32 = 0.3;
+const TOAST_SIZE: Vec2 = vec2(360.0, 60.0);
+
+/// What has to happen to unlock an achievement.
+#[derive(Clone, DeRon)]
+pub enum Condition {
+    /// Enemies shot down, over every run.
+    Kills(u32),
+    /// Seconds lasted in one run.
+    Survive(f32),
+    /// Points in one run.
+    Score(u32),
+    /// Combo multiplier reached in one run, up to the x8 cap.
+    Combo(u32),
+    /// Getting through a wave without firing.
+    WaveWithoutFiring,
+}
+
+impl Condition {
+    fn is_met(&self, progress: &Progress) -> bool {
+        let Progress {
+            lifetime,
+            run,
+            score,
+        } = progress;
+        match *self {
+            Condition::Kills(kills) => lifetime.totals.total_kills() + run.total_kills() >= kills,
+            Condition::Survive(seconds) => run.time_survived >= seconds,
+            Condition::Score(points) => *score >= points,
+            Condition::Combo(multiplier) => scoring::multiplier(run.max_combo) >= multiplier,
+            Condition::WaveWithoutFiring => run.quiet_waves > 0,
+        }
+    }
+}
+
+/// An achievement from `assets/achievements.ron`.
+#[derive(Clone, DeRon)]
+pub struct Achievement {
+    /// Key the unlock is saved under.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+impl Achievement {
+    pub async fn load_all() -> Result<Vec<Achievement>, macroquad::Error> {
+        let achievements = Vec::<Achievement>::deserialize_ron(
+            &load_string(DEFINITIONS_PATH).await?,
+        )
+        .map_err(|err| {
+            error!("{}: {}", DEFINITIONS_PATH, err);
+            macroquad::Error::UnknownError("invalid achievements file")
+        })?;
+        for (i, achievement) in achievements.iter().enumerate() {
+            if achievements[..i]
+                .iter()
+                .any(|other| other.id == achievement.id)
+            {
+                warn!("{}: duplicate id {}", DEFINITIONS_PATH, achievement.id);
+            }
+        }
+        Ok(achievements)
+    }
+}
+
+/// The stats achievements are checked against, while a run is in progress.
+pub struct Progress<'a> {
+    /// Earlier runs, not counting the current one.
+    pub lifetime: &'a LifetimeStats,
+    pub run: &'a RunStats,
+    pub score: u32,
+}
+
+#[derive(Default, DeRon, SerRon)]
+#[nserde(default)]
+struct UnlocksFile {
+    /// When each achievement was unlocked, in seconds since the Unix epoch, by id.
+    unlocked: HashMap<String, u64>,
+}
+
+/// Which achievements the player has, saved between sessions, and the notifications for
+/// ones just unlocked.
+pub struct Achievements {
+    unlocks: UnlocksFile,
+    /// Names waiting to be shown, the first one on screen.
+    toasts: Vec<String>,
+    /// Seconds the first toast has been up.
+    toast_time: f32,
+}
+
+impl Achievements {
+    pub fn load() -> Self {
+        Achievements {
+            unlocks: persist::load(UNLOCKS_FILE).unwrap_or_default(),
+            toasts: vec![],
+            toast_time: 0.0,
+        }
+    }
+
+    /// When the achievement with `id` was unlocked, in seconds since the Unix epoch.
+    pub fn unlocked_at(&self, id: &str) -> Option<u64> {
+        self.unlocks.unlocked.get(id).copied()
+    }
+
+    pub fn unlocked_count(&self) -> usize {
+        self.unlocks.unlocked.len()
+    }
+
+    /// Unlocks every achievement whose condition is now met, and saves them if any were.
+    pub fn check(&mut self, achievements: &[Achievement], progress: Progress) {
+        let mut changed = false;
+        for achievement in achievements {
+            if self.unlocks.unlocked.contains_key(&achievement.id)
+                || !achievement.condition.is_met(&progress)
+            {
+                continue;
+            }
+            let now = miniquad::date::now() as u64;
+            self.unlocks.unlocked.insert(achievement.id.clone(), now);
+            self.toasts.push(achievement.name.clone());
+            changed = true;
+        }
+        if changed {
+            persist::save(UNLOCKS_FILE, &self.unlocks);
+        }
+    }
+
+    /// Shows the notification for the latest unlock at the top of the screen, sliding in and
+    /// out, then moves on to the next one.
+    pub fn draw_toasts(&mut self, delta_time: f32) {
+        let Some(name) = self.toasts.first() else {
+            return;
+        };
+        self.toast_time += delta_time;
+        let shown = (self.toast_time / TOAST_SLIDE)
+            .min((TOAST_DURATION - self.toast_time) / TOAST_SLIDE)
+            .clamp(0.0, 1.0);
+
+        let x = canvas::WIDTH / 2.0 - TOAST_SIZE.x / 2.0;
+        let y = 10.0 - (TOAST_SIZE.y + 10.0) * (1.0 - shown);
+        draw_rectangle(
+            x,
+            y,
+            TOAST_SIZE.x,
+            TOAST_SIZE.y,
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+        draw_rectangle_lines(x, y, TOAST_SIZE.x, TOAST_SIZE.y, 2.0, GOLD);
+        draw_text("Achievement unlocked", x + 12.0, y + 22.0, 20.0, GOLD);
+        draw_text(name, x + 12.0, y + 48.0, 25.0, WHITE);
+
+        if self.toast_time >= TOAST_DURATION {
+            self.toasts.remove(0);
+            self.toast_time = 0.0;
+        }
+    }
+}
+
+/// Checks for new unlocks against the run in progress. Once it has ended it's part of the
+/// lifetime stats, so there's nothing left to check.
+pub fn check_run(game: &mut Game, resources: &Resources) {
+    if !game.in_run() {
+        return;
+    }
+    game.achievements.check(
+        &resources.achievements,
+        Progress {
+            lifetime: &game.lifetime,
+            run: &game.run,
+            score: game.score,
+        },
+    );
+}
+
+/// Checks for new unlocks whenever something happens, on top of the check every tick, so
+/// what ends a run still counts.
+pub fn register_subscribers(events: &mut EventBus) {
+    events.subscribe(|game, resources, _| check_run(game, resources));
+}
+
+/// Formats seconds since the Unix epoch as a UTC date, e.g. 2024-03-09.
+pub fn format_date(timestamp: u64) -> String {
+    // Days to a civil date, from Howard Hinnant's date algorithms
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,30 @@
+use macroquad::audio::{play_sound_once, set_sound_volume, Sound};
+
+use crate::{
+    events::{EventBus, GameEvent},
+    settings::Settings,
+};
+
+/// Music volume during a run.
+const PLAYING_SOUND_VOLUME: f32 = 0.3;
+/// Music volume while paused.
+const PAUSED_SOUND_VOLUME: f32 = 0.1;
+
+/// Sound effects, and the music level for whether a run is in progress.
+pub fn register_subscribers(events: &mut EventBus) {
+    events.subscribe(|game, resources, event| match *event {
+        GameEvent::ShotFired => play_effect(&resources.sound_laser, &game.settings),
+        GameEvent::EnemyKilled { .. } => play_effect(&resources.sound_explosion, &game.settings),
+        GameEvent::StateChanged { playing } => game.set_music_level(if playing {
+            PLAYING_SOUND_VOLUME
+        } else {
+            PAUSED_SOUND_VOLUME
+        }),
+        _ => {}
+    });
+}
+
+fn play_effect(sound: &Sound, settings: &Settings) {
+    play_sound_once(sound);
+    set_sound_volume(sound, 0.4 * settings.sfx_volume);
+}
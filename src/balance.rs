@@ -0,0 +1,232 @@
+#[cfg(debug_assertions)]
+use std::time::SystemTime;
+
+use macroquad::prelude::*;
+use nanoserde::DeRon;
+
+#[cfg(debug_assertions)]
+use crate::{
+    console::{arg, Command, CommandRegistry},
+    ASSETS_FOLDER,
+};
+use file::BalanceFile;
+
+const BALANCE_PATH: &str = "balance.ron";
+/// Seconds between checks of the balance file for changes.
+#[cfg(debug_assertions)]
+const RELOAD_INTERVAL: f32 = 1.0;
+/// Highest ship or enemy speed, in pixels per second. Much faster and things cross the
+/// screen in a frame.
+const MAX_SPEED: f32 = 2000.0;
+const MAX_BULLETS: usize = 100;
+/// Longest time between shots, in seconds.
+const MAX_COOLDOWN: f64 = 10.0;
+
+/// Gameplay numbers that decide how the game feels, loaded from `assets/balance.ron` so they
+/// can be tuned without rebuilding.
+#[derive(Clone)]
+pub struct Balance {
+    /// Ship speed in pixels per second. Bullets fly twice as fast.
+    pub movement_speed: f32,
+    /// Most bullets on screen at once.
+    pub max_bullets: usize,
+    /// Seconds between shots.
+    pub bullet_cooldown: f64,
+    /// Chance of an enemy spawning each frame during a wave.
+    pub spawn_chance: f32,
+    pub enemy_speed_min: f32,
+    pub enemy_speed_max: f32,
+}
+
+// What nanoserde derives for `Option` fields trips this lint. The derived impl sits next to the
+// struct rather than inside it, so only a module around both can allow it.
+#[allow(clippy::question_mark)]
+mod file {
+    use nanoserde::DeRon;
+
+    /// The balance file. Values left out keep their defaults.
+    #[derive(DeRon)]
+    pub struct BalanceFile {
+        pub movement_speed: Option<f32>,
+        pub max_bullets: Option<usize>,
+        pub bullet_cooldown: Option<f64>,
+        pub spawn_chance: Option<f32>,
+        pub enemy_speed_min: Option<f32>,
+        pub enemy_speed_max: Option<f32>,
+    }
+}
+
+impl Balance {
+    /// Names accepted by `set`.
+    #[cfg(debug_assertions)]
+    pub const NAMES: [&'static str; 6] = [
+        "movement_speed",
+        "max_bullets",
+        "bullet_cooldown",
+        "spawn_chance",
+        "enemy_speed_min",
+        "enemy_speed_max",
+    ];
+
+    /// Reads the balance file, falling back to the defaults if it's missing or invalid so a
+    /// bad edit never stops the game from starting.
+    pub async fn load() -> Self {
+        let balance = match load_string(BALANCE_PATH).await {
+            Ok(text) => Balance::parse(&text),
+            Err(err) => Err(err.to_string()),
+        };
+        balance.unwrap_or_else(|err| {
+            error!("{}: {}, using the defaults", BALANCE_PATH, err);
+            Balance::default()
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let file = BalanceFile::deserialize_ron(text).map_err(|err| err.to_string())?;
+        let defaults = Balance::default();
+        let balance = Balance {
+            movement_speed: file.movement_speed.unwrap_or(defaults.movement_speed),
+            max_bullets: file.max_bullets.unwrap_or(defaults.max_bullets),
+            bullet_cooldown: file.bullet_cooldown.unwrap_or(defaults.bullet_cooldown),
+            spawn_chance: file.spawn_chance.unwrap_or(defaults.spawn_chance),
+            enemy_speed_min: file.enemy_speed_min.unwrap_or(defaults.enemy_speed_min),
+            enemy_speed_max: file.enemy_speed_max.unwrap_or(defaults.enemy_speed_max),
+        };
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    /// Checks that the values make a playable game.
+    pub fn validate(&self) -> Result<(), String> {
+        let numbers = [
+            self.movement_speed as f64,
+            self.bullet_cooldown,
+            self.spawn_chance as f64,
+            self.enemy_speed_min as f64,
+            self.enemy_speed_max as f64,
+        ];
+        if !numbers.iter().all(|number| number.is_finite()) {
+            return Err("values must be finite numbers".to_string());
+        }
+        if !(0.0..=MAX_SPEED).contains(&self.movement_speed) || self.movement_speed == 0.0 {
+            return Err(format!(
+                "movement_speed must be above 0 and at most {}",
+                MAX_SPEED
+            ));
+        }
+        if !(1..=MAX_BULLETS).contains(&self.max_bullets) {
+            return Err(format!("max_bullets must be from 1 to {}", MAX_BULLETS));
+        }
+        if !(0.0..=MAX_COOLDOWN).contains(&self.bullet_cooldown) {
+            return Err(format!(
+                "bullet_cooldown must be from 0 to {}",
+                MAX_COOLDOWN
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.spawn_chance) {
+            return Err("spawn_chance must be between 0 and 1".to_string());
+        }
+        if self.enemy_speed_min <= 0.0
+            || self.enemy_speed_max < self.enemy_speed_min
+            || self.enemy_speed_max > MAX_SPEED
+        {
+            return Err(format!(
+                "enemy speeds must be above 0 and at most {}, with the max at least the min",
+                MAX_SPEED
+            ));
+        }
+        Ok(())
+    }
+
+    /// Changes one value by name, e.g. from the console. Invalid values are refused.
+    #[cfg(debug_assertions)]
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("{} isn't a valid value", value))
+        }
+        let mut balance = self.clone();
+        match name {
+            "movement_speed" => balance.movement_speed = parse(value)?,
+            "max_bullets" => balance.max_bullets = parse(value)?,
+            "bullet_cooldown" => balance.bullet_cooldown = parse(value)?,
+            "spawn_chance" => balance.spawn_chance = parse(value)?,
+            "enemy_speed_min" => balance.enemy_speed_min = parse(value)?,
+            "enemy_speed_max" => balance.enemy_speed_max = parse(value)?,
+            _ => return Err(format!("unknown value {}", name)),
+        }
+        balance.validate()?;
+        *self = balance;
+        Ok(())
+    }
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Balance {
+            movement_speed: 200.0,
+            max_bullets: 7,
+            bullet_cooldown: 0.25,
+            spawn_chance: 0.04,
+            enemy_speed_min: 50.0,
+            enemy_speed_max: 150.0,
+        }
+    }
+}
+
+/// Reloads the balance file whenever it's saved, in debug builds.
+#[cfg(debug_assertions)]
+#[derive(Default)]
+pub struct BalanceWatcher {
+    modified: Option<SystemTime>,
+    since_check: f32,
+}
+
+#[cfg(debug_assertions)]
+impl BalanceWatcher {
+    pub fn update(&mut self, balance: &mut Balance, delta_time: f32) {
+        self.since_check += delta_time;
+        if self.since_check < RELOAD_INTERVAL {
+            return;
+        }
+        self.since_check = 0.0;
+
+        let path = format!("{}/{}", ASSETS_FOLDER, BALANCE_PATH);
+        let Ok(modified) = std::fs::metadata(&path).and_then(|file| file.modified()) else {
+            return;
+        };
+        // The first check only notes the time, the file was read at startup
+        let changed = self.modified.is_some_and(|last| last != modified);
+        self.modified = Some(modified);
+        if !changed {
+            return;
+        }
+
+        let reloaded = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Balance::parse(&text));
+        match reloaded {
+            Ok(reloaded) => {
+                *balance = reloaded;
+                info!("reloaded {}", path);
+            }
+            Err(err) => error!("{}: {}, keeping the current values", path, err),
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(Command {
+        name: "set",
+        usage: "<name> <value>",
+        completions: &Balance::NAMES,
+        run: |game, _, args| {
+            let name = arg::<String>(args, 0, "name")?;
+            let value = arg::<String>(args, 1, "value")?;
+            game.balance.set(&name, &value)?;
+            Ok(format!("{} = {}", name, value))
+        },
+    });
+}
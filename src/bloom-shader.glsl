@@ -0,0 +1,27 @@
+#version 100
+
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec2 texel;
+
+#define THRESHOLD .6
+#define STRENGTH 3.
+
+void main()
+{
+    vec3 col = texture2D(Texture, uv).rgb;
+
+    // Blur only the bright parts (lasers, explosions) and add them back on top
+    vec3 glow = vec3(0.);
+    for (int x = -2; x <= 2; x++) {
+        for (int y = -2; y <= 2; y++) {
+            vec3 s = texture2D(Texture, uv + vec2(float(x), float(y)) * texel * 2.).rgb;
+            glow += max(s - THRESHOLD, 0.);
+        }
+    }
+
+    gl_FragColor = vec4(col + glow / 25. * STRENGTH, 1.);
+}
@@ -0,0 +1,74 @@
+use macroquad::prelude::*;
+
+/// Trauma lost per second.
+const TRAUMA_DECAY: f32 = 1.2;
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+/// In degrees.
+const MAX_SHAKE_ANGLE: f32 = 2.0;
+/// Zoom punch lost per second.
+const PUNCH_DECAY: f32 = 0.6;
+/// Fraction of the ship's distance from the centre the view leans towards it.
+const FOLLOW_AMOUNT: f32 = 0.08;
+const FOLLOW_RATE: f32 = 4.0;
+
+/// How the world is framed for one frame, relative to the neutral view of the whole canvas.
+#[derive(Clone, Copy)]
+pub struct View {
+    /// In pixels.
+    pub offset: Vec2,
+    /// In degrees.
+    pub rotation: f32,
+    pub zoom: f32,
+}
+
+impl View {
+    pub const NEUTRAL: View = View {
+        offset: Vec2::ZERO,
+        rotation: 0.0,
+        zoom: 1.0,
+    };
+}
+
+/// Trauma-based shake, zoom punches and a slight lean towards the ship. Shaking grows with
+/// the square of trauma, so small knocks barely register and big ones really rattle.
+#[derive(Default)]
+pub struct GameCamera {
+    trauma: f32,
+    punch: f32,
+    follow: Vec2,
+}
+
+impl GameCamera {
+    /// Adds trauma, from 0 to 1.
+    pub fn shake(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    /// Briefly zooms in by `amount`, e.g. 0.1 for 10%.
+    pub fn punch(&mut self, amount: f32) {
+        self.punch = self.punch.max(amount);
+    }
+
+    pub fn reset(&mut self) {
+        *self = GameCamera::default();
+    }
+
+    /// Decays shake and punch, and leans towards `focus` if there is one.
+    pub fn update(&mut self, focus: Option<Vec2>, center: Vec2, delta_time: f32) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta_time).max(0.0);
+        self.punch = (self.punch - PUNCH_DECAY * delta_time).max(0.0);
+        let target = focus.map_or(Vec2::ZERO, |focus| (focus - center) * FOLLOW_AMOUNT);
+        self.follow += (target - self.follow) * (1.0 - (-FOLLOW_RATE * delta_time).exp());
+    }
+
+    /// `shake_scale` comes from the accessibility setting, 0 turns shaking off.
+    pub fn view(&self, shake_scale: f32) -> View {
+        let shake = self.trauma * self.trauma * shake_scale;
+        let jitter = vec2(rand::gen_range(-1.0, 1.0), rand::gen_range(-1.0, 1.0));
+        View {
+            offset: self.follow + jitter * MAX_SHAKE_OFFSET * shake,
+            rotation: rand::gen_range(-1.0, 1.0) * MAX_SHAKE_ANGLE * shake,
+            zoom: 1.0 + self.punch,
+        }
+    }
+}
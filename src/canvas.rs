@@ -0,0 +1,116 @@
+use macroquad::prelude::*;
+
+use crate::camera::View;
+
+/// Size of the logical playfield. Gameplay and HUD coordinates are always in this space,
+/// whatever the size of the window.
+pub const WIDTH: f32 = 480.0;
+pub const HEIGHT: f32 = 640.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    /// Fill as much of the window as possible while keeping the aspect ratio.
+    Aspect,
+    /// Only scale by whole multiples so pixels stay square, falling back to `Aspect` when the
+    /// window is smaller than the canvas.
+    Integer,
+}
+
+/// Where the canvas is drawn in the window, with black bars around it.
+pub fn viewport(scaling: Scaling) -> Rect {
+    let fit = (screen_width() / WIDTH).min(screen_height() / HEIGHT);
+    let scale = match scaling {
+        Scaling::Integer if fit >= 1.0 => fit.floor(),
+        _ => fit,
+    };
+    let size = vec2(WIDTH, HEIGHT) * scale;
+    Rect::new(
+        ((screen_width() - size.x) / 2.0).floor(),
+        ((screen_height() - size.y) / 2.0).floor(),
+        size.x,
+        size.y,
+    )
+}
+
+/// How much smaller the canvas is in low resolution mode.
+const LOW_RES_DIVISOR: f32 = 2.0;
+
+/// Off-screen target the whole frame is drawn to before being scaled into the window.
+/// Drawing always uses logical coordinates; in low resolution mode they're mapped onto a
+/// smaller target that is upscaled with nearest filtering for chunky pixels.
+pub struct Canvas {
+    target: RenderTarget,
+    low_res: bool,
+}
+
+impl Canvas {
+    pub fn new(low_res: bool) -> Self {
+        let (target, filter) = if low_res {
+            let target = render_target(
+                (WIDTH / LOW_RES_DIVISOR) as u32,
+                (HEIGHT / LOW_RES_DIVISOR) as u32,
+            );
+            (target, FilterMode::Nearest)
+        } else {
+            (
+                render_target(WIDTH as u32, HEIGHT as u32),
+                FilterMode::Linear,
+            )
+        };
+        target.texture.set_filter(filter);
+        Canvas { target, low_res }
+    }
+
+    /// Recreates the target if the resolution setting changed.
+    pub fn set_low_res(&mut self, low_res: bool) {
+        if low_res != self.low_res {
+            *self = Canvas::new(low_res);
+        }
+    }
+
+    /// Size of the target in pixels, for shaders working in `gl_FragCoord` space.
+    pub fn resolution(&self) -> Vec2 {
+        self.target.texture.size()
+    }
+
+    /// Directs drawing to the canvas.
+    pub fn begin(&self) {
+        self.set_view(View::NEUTRAL);
+        clear_background(BLACK);
+    }
+
+    /// Frames whatever is drawn next, e.g. the shaken world and then the steady HUD.
+    pub fn set_view(&self, view: View) {
+        // Y isn't flipped like `Camera2D::from_display_rect` does, as render targets are
+        // stored upside down
+        set_camera(&Camera2D {
+            target: vec2(WIDTH / 2.0, HEIGHT / 2.0) + view.offset,
+            zoom: vec2(2.0 / WIDTH, 2.0 / HEIGHT) * view.zoom,
+            rotation: view.rotation,
+            render_target: Some(self.target.clone()),
+            ..Default::default()
+        });
+    }
+
+    /// The finished frame, once everything has been drawn.
+    pub fn texture(&self) -> &Texture2D {
+        &self.target.texture
+    }
+}
+
+/// Draws a finished frame into the window.
+pub fn present(frame: &Texture2D, scaling: Scaling) {
+    set_default_camera();
+    clear_background(BLACK);
+    let viewport = viewport(scaling);
+    draw_texture_ex(
+        frame,
+        viewport.x,
+        viewport.y,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(viewport.size()),
+            ..Default::default()
+        },
+    );
+}
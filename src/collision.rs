@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+
+use macroquad::{
+    experimental::animation::AnimationFrame,
+    math::{vec2, Rect, Vec2},
+    texture::Image,
+};
+
+/// Number of sides used when a circle has to be approximated by a polygon.
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// Pixels with an alpha at or below this value are ignored when building hitboxes from a mask.
+const ALPHA_THRESHOLD: f32 = 0.1;
+
+/// Cells a grid covers in each direction from the origin. Anything further out is filed in
+/// the edge cells, so a runaway position can't make a query walk an endless range.
+const GRID_EXTENT: f32 = 64.0;
+
+/// A collision shape. Hitboxes are authored in sprite-local space, where the sprite spans
+/// -0.5..0.5 on both axes, and turned into world space with `placed()`.
+#[derive(Clone, Debug)]
+pub enum Hitbox {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    Rect(Rect),
+    /// Convex polygon, in either winding order.
+    Polygon(Vec<Vec2>),
+}
+
+impl Hitbox {
+    pub fn circle(radius: f32) -> Self {
+        Hitbox::Circle {
+            center: Vec2::ZERO,
+            radius,
+        }
+    }
+
+    pub fn rect(w: f32, h: f32) -> Self {
+        Hitbox::Rect(Rect::new(-w / 2.0, -h / 2.0, w, h))
+    }
+
+    /// Builds the convex hull of the opaque pixels of `source` within `image`.
+    pub fn from_alpha(image: &Image, source: Rect) -> Self {
+        let mut points = vec![];
+        for y in 0..source.h as u32 {
+            for x in 0..source.w as u32 {
+                let pixel = image.get_pixel(source.x as u32 + x, source.y as u32 + y);
+                if pixel.a > ALPHA_THRESHOLD {
+                    let (x, y) = (x as f32, y as f32);
+                    points.extend([
+                        vec2(x, y),
+                        vec2(x + 1.0, y),
+                        vec2(x, y + 1.0),
+                        vec2(x + 1.0, y + 1.0),
+                    ]);
+                }
+            }
+        }
+
+        if points.is_empty() {
+            return Hitbox::rect(1.0, 1.0);
+        }
+
+        let size = vec2(source.w, source.h);
+        Hitbox::Polygon(
+            convex_hull(points)
+                .into_iter()
+                .map(|p| p / size - vec2(0.5, 0.5))
+                .collect(),
+        )
+    }
+
+    /// Grows or shrinks a sprite-local hitbox around the sprite's centre.
+    pub fn scaled(&self, factor: f32) -> Self {
+        match self {
+            Hitbox::Circle { center, radius } => Hitbox::Circle {
+                center: *center * factor,
+                radius: radius * factor,
+            },
+            Hitbox::Rect(rect) => Hitbox::Rect(Rect::new(
+                rect.x * factor,
+                rect.y * factor,
+                rect.w * factor,
+                rect.h * factor,
+            )),
+            Hitbox::Polygon(points) => {
+                Hitbox::Polygon(points.iter().map(|p| *p * factor).collect())
+            }
+        }
+    }
+
+    /// Moves a sprite-local hitbox into world space for a sprite drawn at `center` with size `dims`.
+    pub fn placed(&self, center: Vec2, dims: Vec2) -> Self {
+        match self {
+            Hitbox::Circle {
+                center: offset,
+                radius,
+            } => Hitbox::Circle {
+                center: center + *offset * dims,
+                radius: radius * dims.x.min(dims.y),
+            },
+            Hitbox::Rect(rect) => Hitbox::Rect(Rect::new(
+                center.x + rect.x * dims.x,
+                center.y + rect.y * dims.y,
+                rect.w * dims.x,
+                rect.h * dims.y,
+            )),
+            Hitbox::Polygon(points) => {
+                Hitbox::Polygon(points.iter().map(|p| center + *p * dims).collect())
+            }
+        }
+    }
+
+    /// The volume covered while moving from `offset` back to the current position, so fast
+    /// movers can't tunnel through anything between two ticks. Circles become polygons.
+    pub fn swept(&self, offset: Vec2) -> Self {
+        if offset == Vec2::ZERO {
+            return self.clone();
+        }
+
+        let points = self
+            .outline()
+            .into_iter()
+            .flat_map(|p| [p, p + offset])
+            .collect();
+        Hitbox::Polygon(convex_hull(points))
+    }
+
+    fn outline(&self) -> Vec<Vec2> {
+        match self {
+            Hitbox::Circle { center, radius } => {
+                // Circumscribe the circle so the polygon never under-reports a hit
+                let step = std::f32::consts::TAU / CIRCLE_SEGMENTS as f32;
+                let radius = radius / (step / 2.0).cos();
+                (0..CIRCLE_SEGMENTS)
+                    .map(|i| *center + Vec2::from_angle(i as f32 * step) * radius)
+                    .collect()
+            }
+            Hitbox::Rect(rect) => rect_corners(rect).to_vec(),
+            Hitbox::Polygon(points) => points.clone(),
+        }
+    }
+
+    /// Axis-aligned box enclosing the hitbox, used by the broadphase.
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Hitbox::Circle { center, radius } => Rect::new(
+                center.x - radius,
+                center.y - radius,
+                radius * 2.0,
+                radius * 2.0,
+            ),
+            Hitbox::Rect(rect) => *rect,
+            Hitbox::Polygon(points) => {
+                let min = points.iter().fold(Vec2::MAX, |min, p| min.min(*p));
+                let max = points.iter().fold(Vec2::MIN, |max, p| max.max(*p));
+                Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+            }
+        }
+    }
+
+    pub fn collides_with(&self, other: &Hitbox) -> bool {
+        match (self, other) {
+            (
+                Hitbox::Circle {
+                    center: a,
+                    radius: ra,
+                },
+                Hitbox::Circle {
+                    center: b,
+                    radius: rb,
+                },
+            ) => a.distance_squared(*b) <= (ra + rb) * (ra + rb),
+            (Hitbox::Circle { center, radius }, Hitbox::Rect(rect))
+            | (Hitbox::Rect(rect), Hitbox::Circle { center, radius }) => {
+                let closest = center.clamp(rect.point(), rect.point() + rect.size());
+                closest.distance_squared(*center) <= radius * radius
+            }
+            (Hitbox::Rect(a), Hitbox::Rect(b)) => a.overlaps(b),
+            (Hitbox::Circle { center, radius }, Hitbox::Polygon(points))
+            | (Hitbox::Polygon(points), Hitbox::Circle { center, radius }) => {
+                circle_overlaps_polygon(*center, *radius, points)
+            }
+            (Hitbox::Rect(rect), Hitbox::Polygon(points))
+            | (Hitbox::Polygon(points), Hitbox::Rect(rect)) => {
+                polygons_overlap(&rect_corners(rect), points)
+            }
+            (Hitbox::Polygon(a), Hitbox::Polygon(b)) => polygons_overlap(a, b),
+        }
+    }
+}
+
+/// Hitboxes for every frame of a sprite sheet, looked up by the frame currently being drawn.
+pub struct FrameHitboxes {
+    frame_width: f32,
+    frame_height: f32,
+    columns: usize,
+    frames: Vec<Hitbox>,
+}
+
+impl FrameHitboxes {
+    /// Builds a hull from the alpha channel of each `frame_width` x `frame_height` cell of `image`.
+    pub fn from_alpha(image: &Image, frame_width: u32, frame_height: u32) -> Self {
+        let columns = (image.width as u32 / frame_width) as usize;
+        let rows = (image.height as u32 / frame_height) as usize;
+        let mut frames = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                frames.push(Hitbox::from_alpha(
+                    image,
+                    Rect::new(
+                        (column as u32 * frame_width) as f32,
+                        (row as u32 * frame_height) as f32,
+                        frame_width as f32,
+                        frame_height as f32,
+                    ),
+                ));
+            }
+        }
+
+        FrameHitboxes {
+            frame_width: frame_width as f32,
+            frame_height: frame_height as f32,
+            columns,
+            frames,
+        }
+    }
+
+    /// The same hitboxes, each grown or shrunk by `factor`.
+    pub fn scaled(&self, factor: f32) -> Self {
+        FrameHitboxes {
+            frame_width: self.frame_width,
+            frame_height: self.frame_height,
+            columns: self.columns,
+            frames: self
+                .frames
+                .iter()
+                .map(|hitbox| hitbox.scaled(factor))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, frame: &AnimationFrame) -> &Hitbox {
+        let column = (frame.source_rect.x / self.frame_width) as usize;
+        let row = (frame.source_rect.y / self.frame_height) as usize;
+        self.frames
+            .get(row * self.columns + column)
+            .unwrap_or(&self.frames[0])
+    }
+}
+
+/// Uniform grid broadphase. Entities are inserted by index with their bounds each frame, and
+/// queries return the indices of entities sharing at least one cell with the queried bounds.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Empties every cell while keeping their allocations for the next frame.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, bounds: Rect) {
+        let (min, max) = self.cell_range(bounds);
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Fills `out` with the sorted, deduplicated indices of candidates near `bounds`.
+    pub fn query(&self, bounds: Rect, out: &mut Vec<usize>) {
+        out.clear();
+        let (min, max) = self.cell_range(bounds);
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    out.extend_from_slice(cell);
+                }
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    fn cell_range(&self, bounds: Rect) -> ((i32, i32), (i32, i32)) {
+        let cell = |x: f32, y: f32| {
+            (
+                (x / self.cell_size)
+                    .floor()
+                    .clamp(-GRID_EXTENT, GRID_EXTENT) as i32,
+                (y / self.cell_size)
+                    .floor()
+                    .clamp(-GRID_EXTENT, GRID_EXTENT) as i32,
+            )
+        };
+        (
+            cell(bounds.left(), bounds.top()),
+            cell(bounds.right(), bounds.bottom()),
+        )
+    }
+}
+
+fn rect_corners(rect: &Rect) -> [Vec2; 4] {
+    [
+        vec2(rect.left(), rect.top()),
+        vec2(rect.right(), rect.top()),
+        vec2(rect.right(), rect.bottom()),
+        vec2(rect.left(), rect.bottom()),
+    ]
+}
+
+fn project(points: &[Vec2], axis: Vec2) -> (f32, f32) {
+    points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+        let d = p.dot(axis);
+        (min.min(d), max.max(d))
+    })
+}
+
+fn edge_normals(points: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| (*b - *a).perp())
+}
+
+/// Separating axis test between two convex polygons.
+fn polygons_overlap(a: &[Vec2], b: &[Vec2]) -> bool {
+    edge_normals(a).chain(edge_normals(b)).all(|axis| {
+        let (min_a, max_a) = project(a, axis);
+        let (min_b, max_b) = project(b, axis);
+        max_a >= min_b && max_b >= min_a
+    })
+}
+
+fn circle_overlaps_polygon(center: Vec2, radius: f32, points: &[Vec2]) -> bool {
+    if point_in_polygon(center, points) {
+        return true;
+    }
+
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .any(|(a, b)| distance_squared_to_segment(center, *a, *b) <= radius * radius)
+}
+
+fn point_in_polygon(point: Vec2, points: &[Vec2]) -> bool {
+    let mut sign = 0.0;
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let cross = (*b - *a).perp_dot(point - *a);
+        if cross != 0.0 {
+            if sign != 0.0 && cross.signum() != sign {
+                return false;
+            }
+            sign = cross.signum();
+        }
+    }
+    // On the line of a degenerate hull, which the edge distances settle
+    sign != 0.0
+}
+
+fn distance_squared_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (a + ab * t).distance_squared(point)
+}
+
+/// Andrew's monotone chain convex hull.
+fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut lower = half_hull(points.iter());
+    let mut upper = half_hull(points.iter().rev());
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+fn half_hull<'a>(points: impl Iterator<Item = &'a Vec2>) -> Vec<Vec2> {
+    let mut hull: Vec<Vec2> = vec![];
+    for p in points {
+        while let [.., a, b] = hull[..] {
+            if (b - a).perp_dot(*p - a) > 0.0 {
+                break;
+            }
+            hull.pop();
+        }
+        hull.push(*p);
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_bullet_hits_small_enemy_it_passed_in_one_tick() {
+        let enemy = Hitbox::circle(0.5).placed(vec2(100.0, 100.0), vec2(12.0, 12.0));
+        let bullet = Hitbox::rect(1.0, 1.0).placed(vec2(100.0, 20.0), vec2(4.0, 16.0));
+        let offset = vec2(0.0, 160.0);
+        assert!(!bullet.collides_with(&enemy));
+        assert!(bullet.swept(offset).collides_with(&enemy));
+
+        let beside = Hitbox::rect(1.0, 1.0).placed(vec2(120.0, 20.0), vec2(4.0, 16.0));
+        assert!(!beside.swept(offset).collides_with(&enemy));
+    }
+
+    #[test]
+    fn head_on_pair_hits_when_swept_relative_to_each_other() {
+        // The bullet goes up from y 100 to 60 while the enemy comes down from 50 to 110, so
+        // they cross although neither one's own sweep reaches the other
+        let bullet = Hitbox::rect(1.0, 1.0).placed(vec2(0.0, 60.0), vec2(4.0, 4.0));
+        let enemy = Hitbox::circle(0.5).placed(vec2(0.0, 110.0), vec2(12.0, 12.0));
+        let bullet_offset = vec2(0.0, 40.0);
+        let enemy_offset = vec2(0.0, -60.0);
+        assert!(!bullet.swept(bullet_offset).collides_with(&enemy));
+        assert!(bullet
+            .swept(bullet_offset - enemy_offset)
+            .collides_with(&enemy));
+    }
+
+    #[test]
+    fn degenerate_hull_overlaps_circles_only_along_its_segment() {
+        // A sliver swept along its own length has a hull of just two points
+        let sliver = Hitbox::Rect(Rect::new(0.0, 0.0, 0.0, 10.0)).swept(vec2(0.0, 50.0));
+        let Hitbox::Polygon(points) = &sliver else {
+            panic!("sweeps are polygons");
+        };
+        assert_eq!(points.len(), 2);
+
+        let circle = |x, y| Hitbox::Circle {
+            center: vec2(x, y),
+            radius: 2.0,
+        };
+        assert!(sliver.collides_with(&circle(0.0, 30.0)));
+        assert!(sliver.collides_with(&circle(1.5, 30.0)));
+        assert!(sliver.collides_with(&circle(0.0, 61.0)));
+        assert!(!sliver.collides_with(&circle(3.0, 30.0)));
+        assert!(!sliver.collides_with(&circle(0.0, 100.0)));
+        assert!(!sliver.collides_with(&circle(0.0, -10.0)));
+    }
+}
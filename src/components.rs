@@ -0,0 +1,130 @@
+use macroquad::{color::Color, math::Vec2};
+use macroquad_particles::Emitter;
+
+use crate::{collision::Hitbox, ecs::Entity, particles::ParticleEffect, sprites::SpriteSheet};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EnemyKind {
+    Small,
+    Medium,
+    Big,
+}
+
+impl EnemyKind {
+    pub fn from_size(size: f32) -> Self {
+        if size < 32. {
+            EnemyKind::Small
+        } else if size < 48. {
+            EnemyKind::Medium
+        } else {
+            EnemyKind::Big
+        }
+    }
+
+    /// Index into the per-kind sprite, texture and hitbox arrays.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Position of the centre of an entity and the size it is drawn at.
+pub struct Transform {
+    pub pos: Vec2,
+    /// Position at the start of the current tick, used for swept collisions.
+    pub prev: Vec2,
+    pub size: Vec2,
+}
+
+impl Transform {
+    pub fn new(pos: Vec2, size: Vec2) -> Self {
+        Transform {
+            pos,
+            prev: pos,
+            size,
+        }
+    }
+}
+
+pub struct Velocity(pub Vec2);
+
+/// Keeps an entity at a fixed offset from another one.
+pub struct Anchor {
+    pub target: Entity,
+    pub offset: Vec2,
+}
+
+/// Despawns an entity once it has moved `margin` past the screen edge it is heading towards.
+pub struct Cull {
+    pub margin: f32,
+}
+
+pub enum SpriteKind {
+    Sheet(SpriteSheet),
+    /// A filled circle, used for pickups.
+    Orb(Color),
+}
+
+pub struct Sprite {
+    pub kind: SpriteKind,
+    /// Draw order; higher values are drawn on top.
+    pub z: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Player,
+    Enemy,
+    PlayerShot,
+    Pickup,
+}
+
+pub enum ColliderShape {
+    /// Per-frame hitboxes of the entity's current animation frame.
+    Frames(SpriteSheet),
+    /// A single sprite-local hitbox.
+    Fixed(Hitbox),
+}
+
+pub struct Collider {
+    pub layer: Layer,
+    pub shape: ColliderShape,
+}
+
+pub struct Health(pub i32);
+
+pub struct ScoreValue {
+    pub points: u32,
+}
+
+/// Marks an enemy the ship has already come close to, so each close call counts once.
+pub struct Grazed;
+
+/// Emitter checked out of `Effects`, which takes it back when the component is removed.
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    pub emitter: Emitter,
+    pub z: i32,
+    /// Particles set aside for this emitter in the budget. `amount` may be lowered below it.
+    pub reserved: u32,
+    /// Seconds since the emitter stopped emitting.
+    pub stopped_for: f32,
+}
+
+impl ParticleEmitter {
+    /// Whether the emitter has stopped and its last particles have died out.
+    pub fn is_finished(&self) -> bool {
+        !self.emitter.config.emitting && self.stopped_for >= self.emitter.config.lifetime
+    }
+
+    /// Rough number of particles alive. The crate doesn't expose the count, so this takes the
+    /// emitter to be full while emitting and its particles to die off evenly once it stops.
+    #[cfg(debug_assertions)]
+    pub fn live_estimate(&self) -> u32 {
+        let config = &self.emitter.config;
+        if config.emitting {
+            return config.amount;
+        }
+        let left = (1.0 - self.stopped_for / config.lifetime).max(0.0);
+        (config.amount as f32 * left) as u32
+    }
+}
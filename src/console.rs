@@ -0,0 +1,231 @@
+use macroquad::prelude::*;
+
+use crate::{canvas, game::Game, resources::Resources};
+
+const HEIGHT: f32 = 240.0;
+const TEXT_SIZE: f32 = 16.0;
+/// Lines of output kept around.
+const LOG_LENGTH: usize = 100;
+
+/// Runs a command with the words after its name. The message is shown in the console.
+pub type CommandFn = fn(&mut Game, &Resources, &[&str]) -> Result<String, String>;
+
+pub struct Command {
+    pub name: &'static str,
+    /// Arguments, shown by `help`.
+    pub usage: &'static str,
+    /// Words offered by autocomplete for the first argument.
+    pub completions: &'static [&'static str],
+    pub run: CommandFn,
+}
+
+/// Every command the console knows. Modules add their own with a `register_commands`
+/// function.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+impl CommandRegistry {
+    pub fn register(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    fn find(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    fn run(&self, line: &str, game: &mut Game, resources: &Resources) -> Result<String, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let Some((&name, args)) = words.split_first() else {
+            return Ok(String::new());
+        };
+        if name == "help" {
+            let mut help = self
+                .commands
+                .iter()
+                .map(|command| format!("{} {}", command.name, command.usage))
+                .collect::<Vec<_>>();
+            help.sort();
+            return Ok(help.join("\n"));
+        }
+        let command = self
+            .find(name)
+            .ok_or_else(|| format!("unknown command {}, try help", name))?;
+        (command.run)(game, resources, args)
+    }
+
+    /// Completes the last word of `line`, as far as all the candidates agree.
+    fn complete(&self, line: &str) -> Option<String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let partial = if line.ends_with(' ') {
+            ""
+        } else {
+            words.last().copied().unwrap_or("")
+        };
+        let position = if partial.is_empty() {
+            words.len()
+        } else {
+            words.len() - 1
+        };
+
+        let candidates = match position {
+            0 => self
+                .commands
+                .iter()
+                .map(|command| command.name)
+                .chain(["help"])
+                .collect::<Vec<_>>(),
+            1 => self.find(words[0])?.completions.to_vec(),
+            _ => return None,
+        };
+        let mut matches = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(partial));
+        let first = matches.next()?;
+        let common = matches.fold(first.len(), |common, candidate| {
+            first
+                .chars()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| a == b)
+                .count()
+                .min(common)
+        });
+
+        let mut completed = line[..line.len() - partial.len()].to_string();
+        completed.push_str(&first[..common]);
+        if common == first.len() {
+            completed.push(' ');
+        }
+        Some(completed)
+    }
+}
+
+/// Drop-down developer console, opened with the backtick key. The game is frozen while it's
+/// open, and Up/Down go through earlier commands and Tab completes the current word.
+pub struct Console {
+    registry: CommandRegistry,
+    open: bool,
+    input: String,
+    log: Vec<String>,
+    history: Vec<String>,
+    /// Entry of `history` shown in the input, while going through it.
+    browsing: Option<usize>,
+}
+
+impl Console {
+    pub fn new(registry: CommandRegistry) -> Self {
+        Console {
+            registry,
+            open: false,
+            input: String::new(),
+            log: vec!["Type help for a list of commands".to_string()],
+            history: vec![],
+            browsing: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn update(&mut self, game: &mut Game, resources: &Resources) {
+        if is_key_pressed(KeyCode::GraveAccent) {
+            self.open = !self.open;
+            // Don't type the backtick that opened it
+            while get_char_pressed().is_some() {}
+            return;
+        }
+        if !self.open {
+            return;
+        }
+
+        while let Some(c) = get_char_pressed() {
+            if !c.is_control() {
+                self.input.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        let completed = is_key_pressed(KeyCode::Tab)
+            .then(|| self.registry.complete(&self.input))
+            .flatten();
+        if let Some(completed) = completed {
+            self.input = completed;
+        }
+        if is_key_pressed(KeyCode::Up) && !self.history.is_empty() {
+            let entry = self
+                .browsing
+                .map_or(self.history.len() - 1, |entry| entry.saturating_sub(1));
+            self.browsing = Some(entry);
+            self.input = self.history[entry].clone();
+        }
+        let browsing = self.browsing.filter(|_| is_key_pressed(KeyCode::Down));
+        if let Some(entry) = browsing {
+            self.browsing = (entry + 1 < self.history.len()).then_some(entry + 1);
+            self.input = self
+                .browsing
+                .map_or_else(String::new, |entry| self.history[entry].clone());
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            self.submit(game, resources);
+        }
+    }
+
+    fn submit(&mut self, game: &mut Game, resources: &Resources) {
+        let line = std::mem::take(&mut self.input);
+        self.browsing = None;
+        if line.trim().is_empty() {
+            return;
+        }
+
+        self.log.push(format!("> {}", line));
+        let output = match self.registry.run(&line, game, resources) {
+            Ok(message) => message,
+            Err(err) => format!("error: {}", err),
+        };
+        self.log.extend(output.lines().map(str::to_string));
+        let excess = self.log.len().saturating_sub(LOG_LENGTH);
+        self.log.drain(..excess);
+        if self.history.last() != Some(&line) {
+            self.history.push(line);
+        }
+    }
+
+    pub fn draw(&self) {
+        if !self.open {
+            return;
+        }
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            canvas::WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+        draw_line(0.0, HEIGHT, canvas::WIDTH, HEIGHT, 1.0, GRAY);
+        let input_y = HEIGHT - TEXT_SIZE / 2.0;
+        draw_text(
+            format!("> {}_", self.input).as_str(),
+            8.0,
+            input_y,
+            TEXT_SIZE,
+            WHITE,
+        );
+        for (i, line) in self.log.iter().rev().enumerate() {
+            let y = input_y - (i + 1) as f32 * TEXT_SIZE;
+            if y < TEXT_SIZE {
+                break;
+            }
+            draw_text(line, 8.0, y, TEXT_SIZE, LIGHTGRAY);
+        }
+    }
+}
+
+/// Parses the argument at `index`, with its name for the error message.
+pub fn arg<T: std::str::FromStr>(args: &[&str], index: usize, name: &str) -> Result<T, String> {
+    let arg = args.get(index).ok_or_else(|| format!("missing {}", name))?;
+    arg.parse().map_err(|_| format!("bad {}: {}", name, arg))
+}
@@ -0,0 +1,33 @@
+#version 100
+
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+
+#define CURVATURE 4.
+#define VIGNETTE .25
+
+vec2 Curve(vec2 p) {
+    p = p * 2. - 1.;
+    vec2 offset = abs(p.yx) / CURVATURE;
+    p += p * offset * offset;
+    return p * .5 + .5;
+}
+
+void main()
+{
+    vec2 p = Curve(uv);
+    if (p.x < 0. || p.x > 1. || p.y < 0. || p.y > 1.) {
+        gl_FragColor = vec4(0., 0., 0., 1.);
+        return;
+    }
+
+    vec3 col = texture2D(Texture, p).rgb;
+    // Darken towards the rounded corners of the tube
+    vec2 edge = p * (1. - p);
+    col *= pow(edge.x * edge.y * 16., VIGNETTE);
+
+    gl_FragColor = vec4(col, 1.);
+}
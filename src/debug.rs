@@ -0,0 +1,159 @@
+use macroquad::prelude::*;
+
+use crate::{
+    camera::View,
+    canvas::{self, Canvas},
+    collision::Hitbox,
+    components::Layer,
+    game::Game,
+    resources::Resources,
+    scenes::SceneStack,
+    sprites::SpriteSheet,
+    systems,
+};
+
+/// Frames of history in the frame time graph.
+const FRAME_HISTORY: usize = 120;
+/// Height of the graph per millisecond.
+const GRAPH_SCALE: f32 = 2.0;
+const TEXT_SIZE: f32 = 16.0;
+
+/// Developer overlay, only built into debug builds. F3 shows collision shapes and stats, and
+/// while it's shown F4 pauses the simulation and F5 steps it a frame at a time.
+pub struct DebugOverlay {
+    visible: bool,
+    paused: bool,
+    /// Seed the random number generator was started with, to reproduce a run.
+    seed: u64,
+    /// Recent frame times in seconds, oldest first.
+    frame_times: Vec<f32>,
+}
+
+impl DebugOverlay {
+    pub fn new(seed: u64) -> Self {
+        DebugOverlay {
+            visible: false,
+            paused: false,
+            seed,
+            frame_times: Vec::with_capacity(FRAME_HISTORY),
+        }
+    }
+
+    /// Handles the debug keys. Returns whether the simulation should advance this frame.
+    pub fn update(&mut self) -> bool {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.remove(0);
+        }
+        self.frame_times.push(get_frame_time());
+
+        if is_key_pressed(KeyCode::F3) {
+            self.visible = !self.visible;
+            self.paused = false;
+        }
+        if !self.visible {
+            return true;
+        }
+        if is_key_pressed(KeyCode::F4) {
+            self.paused = !self.paused;
+        }
+        !self.paused || is_key_pressed(KeyCode::F5)
+    }
+
+    pub fn draw(&self, game: &Game, scenes: &SceneStack, resources: &Resources, canvas: &Canvas) {
+        if !self.visible {
+            return;
+        }
+
+        canvas.set_view(game.view);
+        self.draw_hitboxes(game, resources);
+        canvas.set_view(View::NEUTRAL);
+
+        let effects = game.world.effects.stats(&game.world.emitters);
+        let lines = [
+            format!("FPS {}  seed {}", get_fps(), self.seed),
+            format!(
+                "Scene {}  wave {}{}",
+                scenes.scene_name(),
+                game.wave,
+                if self.paused {
+                    "  PAUSED (F5 steps)"
+                } else {
+                    ""
+                }
+            ),
+            format!("Entities {}", game.world.entity_count()),
+            format!(
+                "Particles ~{} alive ({} reserved)  emitters {} (+{} pooled)",
+                effects.live, effects.reserved, effects.active, effects.pooled
+            ),
+            format!("Effects degraded {}", effects.degraded),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 10.0, 60.0 + i as f32 * TEXT_SIZE, TEXT_SIZE, YELLOW);
+        }
+        self.draw_frame_times();
+    }
+
+    fn draw_hitboxes(&self, game: &Game, resources: &Resources) {
+        let world = &game.world;
+        for (entity, collider) in world.colliders.iter() {
+            let Some(transform) = world.transforms.get(entity) else {
+                continue;
+            };
+            let color = match collider.layer {
+                Layer::Player => GREEN,
+                Layer::Enemy => RED,
+                Layer::PlayerShot => SKYBLUE,
+                Layer::Pickup => VIOLET,
+            };
+            let hitbox = systems::hitbox(collider, transform, resources, &game.sheets);
+            draw_hitbox(&hitbox, color);
+
+            if collider.layer == Layer::Player {
+                let graze = resources
+                    .ship_graze_hitboxes
+                    .get(&game.sheets.frame(SpriteSheet::Ship))
+                    .placed(transform.pos, transform.size);
+                draw_hitbox(&graze, YELLOW);
+            }
+        }
+        for obstacle in game.obstacles() {
+            draw_hitbox(&Hitbox::Rect(*obstacle), ORANGE);
+        }
+    }
+
+    /// Bars of recent frame times along the bottom right, with a line at 60 FPS.
+    fn draw_frame_times(&self) {
+        let left = canvas::WIDTH - FRAME_HISTORY as f32 - 10.0;
+        let bottom = canvas::HEIGHT - 10.0;
+        for (i, frame_time) in self.frame_times.iter().enumerate() {
+            let height = frame_time * 1000.0 * GRAPH_SCALE;
+            let color = if *frame_time > 1.0 / 55.0 { RED } else { GREEN };
+            draw_line(
+                left + i as f32,
+                bottom,
+                left + i as f32,
+                bottom - height,
+                1.0,
+                color,
+            );
+        }
+        let target = bottom - 1000.0 / 60.0 * GRAPH_SCALE;
+        draw_line(left, target, canvas::WIDTH - 10.0, target, 1.0, WHITE);
+    }
+}
+
+fn draw_hitbox(hitbox: &Hitbox, color: Color) {
+    match hitbox {
+        Hitbox::Circle { center, radius } => {
+            draw_circle_lines(center.x, center.y, *radius, 1.0, color)
+        }
+        Hitbox::Rect(rect) => draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.0, color),
+        Hitbox::Polygon(points) => {
+            for (i, a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                draw_line(a.x, a.y, b.x, b.y, 1.0, color);
+            }
+        }
+    }
+}
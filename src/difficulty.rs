@@ -0,0 +1,120 @@
+/// Lowest and highest intensity adaptive difficulty goes to.
+const MIN_INTENSITY: f32 = 0.6;
+const MAX_INTENSITY: f32 = 1.6;
+const KILL_INTENSITY: f32 = 0.01;
+/// Close calls suggest the player is struggling.
+const GRAZE_INTENSITY: f32 = -0.03;
+const DEATH_INTENSITY: f32 = -0.2;
+/// Seconds over which intensity settles back to normal, so only recent play counts.
+const INTENSITY_MEMORY: f32 = 30.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Insane => "Insane",
+        }
+    }
+
+    /// Index into per-difficulty tables such as the high scores.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Multiplier for how often enemies spawn.
+    pub fn spawn_rate(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.4,
+            Difficulty::Insane => 2.0,
+        }
+    }
+
+    /// Multiplier for how fast enemies fly.
+    pub fn enemy_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Insane => 1.5,
+        }
+    }
+
+    /// Hits it takes to shoot an enemy down.
+    pub fn enemy_health(self) -> i32 {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+            Difficulty::Insane => 3,
+        }
+    }
+
+    /// Multiplier for how many bullets the ship can have on screen at once.
+    pub fn bullet_density(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.8,
+            Difficulty::Insane => 0.6,
+        }
+    }
+}
+
+/// Dynamic difficulty. Intensity rises while the player shoots enemies down and falls with
+/// close calls and deaths, then settles back to 1 over time. It carries over between runs.
+pub struct AdaptiveDifficulty {
+    intensity: f32,
+}
+
+impl AdaptiveDifficulty {
+    /// Multiplier for the spawn rate, around 1.
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn kill(&mut self) {
+        self.change(KILL_INTENSITY);
+    }
+
+    pub fn graze(&mut self) {
+        self.change(GRAZE_INTENSITY);
+    }
+
+    pub fn death(&mut self) {
+        self.change(DEATH_INTENSITY);
+    }
+
+    /// Settles intensity back towards 1.
+    pub fn update(&mut self, delta_time: f32) {
+        let change = (1.0 - self.intensity) * (1.0 - (-delta_time / INTENSITY_MEMORY).exp());
+        self.change(change);
+    }
+
+    fn change(&mut self, change: f32) {
+        self.intensity = (self.intensity + change).clamp(MIN_INTENSITY, MAX_INTENSITY);
+    }
+}
+
+impl Default for AdaptiveDifficulty {
+    fn default() -> Self {
+        AdaptiveDifficulty { intensity: 1.0 }
+    }
+}
@@ -0,0 +1,23 @@
+#version 100
+
+precision highp float;
+
+uniform float progress;
+uniform float block_size;
+
+float Hash21(vec2 p) {
+    p = fract(p * vec2(123.34, 456.21));
+    p += dot(p, p + 45.32);
+    return fract(p.x * p.y);
+}
+
+void main()
+{
+    // Each block of pixels turns black once progress passes its random threshold
+    vec2 block = floor(gl_FragCoord.xy / block_size);
+    if (Hash21(block) >= progress) {
+        discard;
+    }
+
+    gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,136 @@
+use crate::{
+    components::{
+        Anchor, Collider, Cull, Grazed, Health, ParticleEmitter, ScoreValue, Sprite, Transform,
+        Velocity,
+    },
+    effects::Effects,
+    pool::{Handle, Pool},
+};
+
+pub type Entity = Handle<()>;
+
+/// Storage for one component type, indexed by entity slot.
+pub struct Components<T> {
+    slots: Vec<Option<(Entity, T)>>,
+}
+
+impl<T> Components<T> {
+    pub fn new() -> Self {
+        Components { slots: vec![] }
+    }
+
+    pub fn insert(&mut self, entity: Entity, value: T) {
+        let index = entity.index();
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+        self.slots[index] = Some((entity, value));
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slots.get_mut(entity.index())?;
+        match slot {
+            Some((owner, _)) if *owner == entity => slot.take().map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index()) {
+            Some(Some((owner, value))) if *owner == entity => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index()) {
+            Some(Some((owner, value))) if *owner == entity => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.as_ref().map(|(entity, value)| (*entity, value)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.slots
+            .iter_mut()
+            .filter_map(|slot| slot.as_mut().map(|(entity, value)| (*entity, value)))
+    }
+}
+
+impl<T> Default for Components<T> {
+    fn default() -> Self {
+        Components::new()
+    }
+}
+
+/// Every game entity is an id plus whichever components it has been given.
+#[derive(Default)]
+pub struct World {
+    entities: Pool<()>,
+    pub transforms: Components<Transform>,
+    pub velocities: Components<Velocity>,
+    pub anchors: Components<Anchor>,
+    pub culls: Components<Cull>,
+    pub sprites: Components<Sprite>,
+    pub colliders: Components<Collider>,
+    pub healths: Components<Health>,
+    pub score_values: Components<ScoreValue>,
+    pub grazed: Components<Grazed>,
+    pub emitters: Components<ParticleEmitter>,
+    pub effects: Effects,
+}
+
+impl World {
+    pub fn spawn(&mut self) -> Entity {
+        self.entities.insert(())
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.contains(entity)
+    }
+
+    pub fn despawn(&mut self, entity: Entity) {
+        if self.entities.remove(entity).is_none() {
+            return;
+        }
+        self.transforms.remove(entity);
+        self.velocities.remove(entity);
+        self.anchors.remove(entity);
+        self.culls.remove(entity);
+        self.sprites.remove(entity);
+        self.colliders.remove(entity);
+        self.healths.remove(entity);
+        self.score_values.remove(entity);
+        self.grazed.remove(entity);
+        self.remove_emitter(entity);
+    }
+
+    /// Removes an entity's particle emitter and hands it back to `effects`.
+    pub fn remove_emitter(&mut self, entity: Entity) {
+        if let Some(emitter) = self.emitters.remove(entity) {
+            self.effects.recycle(emitter);
+        }
+    }
+
+    /// Despawns everything. Generations are kept, so old handles stay invalid.
+    pub fn clear(&mut self) {
+        let entities = self
+            .entities
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in entities {
+            self.despawn(entity);
+        }
+    }
+}
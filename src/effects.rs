@@ -0,0 +1,171 @@
+use macroquad::math::Vec2;
+use macroquad_particles::{Emitter, EmitterConfig};
+
+#[cfg(debug_assertions)]
+use crate::ecs::Components;
+use crate::{
+    components::{EnemyKind, ParticleEmitter},
+    events::{EventBus, GameEvent},
+    particles::{ParticleEffect, ParticlePresets},
+    prefabs,
+};
+
+/// Most particles that may be alive at once, across every emitter.
+const PARTICLE_BUDGET: u32 = 2000;
+/// Share of the particle budget above which cosmetic effects are skipped.
+const PARTICLE_BUDGET_COSMETIC: f32 = 0.75;
+/// Effects that would get fewer particles than this to fit the budget aren't shown at all.
+const MIN_EMITTER_PARTICLES: u32 = 8;
+/// Chromatic aberration flashed on the screen for each kill.
+const KILL_ABERRATION: f32 = 0.4;
+/// Screen shake trauma added for each kill.
+const KILL_TRAUMA: f32 = 0.25;
+/// Screen shake trauma added when the ship takes a hit.
+const SHIP_HIT_TRAUMA: f32 = 0.8;
+/// Camera zoom punch when a big enemy goes down.
+const BOSS_KILL_PUNCH: f32 = 0.08;
+
+/// Hands out particle emitters and takes them back. Creating an emitter sets up GPU buffers
+/// that are never freed, so every emitter handed back is kept per effect and reused.
+///
+/// Every emitter in use reserves its `amount` of particles from a global budget. When the
+/// budget runs low, cosmetic effects are skipped first, then new effects get fewer particles.
+#[derive(Default)]
+pub struct Effects {
+    free: [Vec<Emitter>; ParticleEffect::ALL.len()],
+    active: usize,
+    /// Particles reserved by emitters in use. Each one never has more than its amount alive.
+    reserved: u32,
+    /// Effects skipped or cut down to fit the budget, since the game started.
+    degraded: u32,
+}
+
+/// Snapshot of `Effects` for the debug overlay.
+#[cfg(debug_assertions)]
+pub struct EffectStats {
+    /// Estimated, see `ParticleEmitter::live_estimate`.
+    pub live: u32,
+    pub active: usize,
+    pub pooled: usize,
+    pub reserved: u32,
+    pub degraded: u32,
+}
+
+impl Effects {
+    /// An emitter for `effect`, or None if the budget can't spare any particles for it.
+    pub fn spawn(
+        &mut self,
+        presets: &ParticlePresets,
+        effect: ParticleEffect,
+        z: i32,
+    ) -> Option<ParticleEmitter> {
+        let mut config = presets.config(effect);
+        let available = PARTICLE_BUDGET.saturating_sub(self.reserved);
+        let crowded = self.reserved as f32 > PARTICLE_BUDGET as f32 * PARTICLE_BUDGET_COSMETIC;
+        if available < config.amount {
+            self.degraded += 1;
+            config.amount = available;
+        } else if crowded && effect.is_cosmetic() {
+            self.degraded += 1;
+            return None;
+        }
+        if config.amount < MIN_EMITTER_PARTICLES {
+            return None;
+        }
+
+        self.active += 1;
+        self.reserved += config.amount;
+        let emitter = self.take(effect, config);
+        Some(ParticleEmitter {
+            effect,
+            reserved: emitter.config.amount,
+            emitter,
+            z,
+            stopped_for: 0.0,
+        })
+    }
+
+    /// Returns an emitter to the pool. Particles it still has alive are cleared first, or
+    /// they would show up again wherever the emitter is used next.
+    pub fn recycle(&mut self, emitter: ParticleEmitter) {
+        self.active -= 1;
+        self.reserved -= emitter.reserved;
+        let finished = emitter.is_finished();
+        let mut inner = emitter.emitter;
+        if !finished {
+            clear_particles(&mut inner);
+        }
+        self.free[emitter.effect as usize].push(inner);
+    }
+
+    /// A pooled emitter for `effect` set up with `config`, or a new one. This is outside the
+    /// budget, for emitters that aren't part of the world.
+    pub fn take(&mut self, effect: ParticleEffect, config: EmitterConfig) -> Emitter {
+        match self.free[effect as usize].pop() {
+            Some(mut emitter) => {
+                emitter.config = config;
+                emitter
+            }
+            None => Emitter::new(config),
+        }
+    }
+
+    /// Returns an emitter from `take` to the pool.
+    pub fn put_back(&mut self, effect: ParticleEffect, mut emitter: Emitter) {
+        clear_particles(&mut emitter);
+        self.free[effect as usize].push(emitter);
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn stats(&self, emitters: &Components<ParticleEmitter>) -> EffectStats {
+        EffectStats {
+            live: emitters
+                .iter()
+                .map(|(_, emitter)| emitter.live_estimate())
+                .sum(),
+            active: self.active,
+            pooled: self.free.iter().map(Vec::len).sum(),
+            reserved: self.reserved,
+            degraded: self.degraded,
+        }
+    }
+}
+
+/// Kills every particle `emitter` has alive and stops it. `Emitter::reset` is private, but an
+/// update drops particles older than the configured lifetime, and drawing runs one.
+fn clear_particles(emitter: &mut Emitter) {
+    let lifetime = emitter.config.lifetime;
+    emitter.config.emitting = false;
+    emitter.config.lifetime = -1.0;
+    emitter.draw(Vec2::ZERO);
+    emitter.config.lifetime = lifetime;
+}
+
+/// Particles, screen shake and aberration for hits and kills.
+pub fn register_subscribers(events: &mut EventBus) {
+    events.subscribe(|game, resources, event| match *event {
+        GameEvent::EnemyHit { pos } => {
+            prefabs::burst(&mut game.world, resources, pos, ParticleEffect::HitSpark);
+        }
+        GameEvent::EnemyKilled { pos, kind, .. } => {
+            let effect = if kind == EnemyKind::Big {
+                ParticleEffect::ExplosionBig
+            } else {
+                ParticleEffect::ExplosionSmall
+            };
+            prefabs::burst(&mut game.world, resources, pos, effect);
+            game.aberration = game.aberration.max(KILL_ABERRATION);
+            game.camera.shake(KILL_TRAUMA);
+            if game.is_boss_wave() {
+                game.camera.punch(BOSS_KILL_PUNCH);
+            }
+        }
+        GameEvent::PlayerHit {
+            destroyed: true, ..
+        } => {
+            game.aberration = 1.0;
+            game.camera.shake(SHIP_HIT_TRAUMA);
+        }
+        _ => {}
+    });
+}
@@ -0,0 +1,72 @@
+use macroquad::math::Vec2;
+
+use crate::{components::EnemyKind, game::Game, resources::Resources, systems::DeathCause};
+
+/// Something that happened in the game that other parts of it may want to react to.
+#[derive(Clone, Copy)]
+pub enum GameEvent {
+    ShotFired,
+    /// A shot hit an enemy, whether or not it went down.
+    EnemyHit {
+        pos: Vec2,
+    },
+    EnemyKilled {
+        pos: Vec2,
+        kind: EnemyKind,
+        /// Points before any multiplier.
+        points: u32,
+    },
+    /// The ship came close to an enemy, for the first time with that enemy.
+    Grazed,
+    /// Something hit the ship. It's only `destroyed` if god mode didn't save it.
+    PlayerHit {
+        cause: DeathCause,
+        destroyed: bool,
+    },
+    /// The ship picked up a power-up.
+    PowerupCollected,
+    /// `Game::wave` has the new wave's number.
+    WaveStarted,
+    /// A scene change started the run or stopped it, e.g. pausing or game over.
+    StateChanged {
+        playing: bool,
+    },
+}
+
+/// Reacts to an event. Subscribers may emit further events, which are handled in the same
+/// dispatch.
+pub type Subscriber = fn(&mut Game, &Resources, &GameEvent);
+
+/// Events waiting to be dispatched, and everything subscribed to them. Gameplay only emits
+/// events, so reacting to one doesn't mean touching the code that noticed it. Modules add
+/// their reactions with a `register_subscribers` function.
+#[derive(Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    pub fn subscribe(&mut self, subscriber: Subscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    pub fn emit(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+}
+
+/// Hands every queued event to every subscriber, in the order they subscribed.
+pub fn dispatch(game: &mut Game, resources: &Resources) {
+    // Subscribers get the game mutably, so they're taken out while they run
+    let subscribers = std::mem::take(&mut game.events.subscribers);
+    while !game.events.queue.is_empty() {
+        let events = std::mem::take(&mut game.events.queue);
+        for event in &events {
+            for subscriber in &subscribers {
+                subscriber(game, resources, event);
+            }
+        }
+    }
+    game.events.subscribers = subscribers;
+}
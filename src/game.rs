@@ -0,0 +1,560 @@
+use macroquad::audio::set_sound_volume;
+use macroquad::prelude::*;
+
+#[cfg(debug_assertions)]
+use crate::console::{arg, Command, CommandRegistry};
+use crate::{
+    achievements::{self, Achievements},
+    balance::Balance,
+    camera::{GameCamera, View},
+    canvas::{self, Canvas},
+    components::Layer,
+    difficulty::{AdaptiveDifficulty, Difficulty},
+    draw_text_centered,
+    ecs::{Entity, World},
+    events::{EventBus, GameEvent},
+    parallax::Parallax,
+    particles::ParticleEffect,
+    prefabs,
+    resources::Resources,
+    scenery::Scenery,
+    scoring::{Combo, ScorePopups},
+    settings::Settings,
+    sprites::SpriteSheets,
+    starfield::StarfieldParams,
+    stats::{LifetimeStats, RunStats},
+    systems::{self, CollisionSystem},
+};
+
+/// Music volume change per second when moving between scenes.
+const MUSIC_FADE_SPEED: f32 = 0.5;
+/// Engine flames, relative to the centre of the ship sprite.
+const SHIP_THRUSTERS: [Vec2; 2] = [vec2(-7.0, 18.0), vec2(7.0, 18.0)];
+/// How much narrower the ship looks while banking, for placing the thrusters.
+const BANK_SQUASH: f32 = 0.6;
+/// Extra thrust of the engine on the outside of a turn.
+const BANK_THRUST: f32 = 0.3;
+/// Side of a cell of the broad-phase collision grid.
+const COLLISION_CELL_SIZE: f32 = 64.0;
+/// Chromatic aberration lost per second.
+const ABERRATION_DECAY: f32 = 2.0;
+/// Seconds of enemies spawning in each wave, followed by a break with none.
+const WAVE_LENGTH: f32 = 30.0;
+/// Seconds of quiet between waves.
+const WAVE_BREAK: f32 = 5.0;
+/// Seconds at the end of a wave break spent streaking through hyperspace to the next level.
+const LEVEL_WARP_TIME: f32 = 1.5;
+/// Every this many waves only big enemies come.
+const BOSS_WAVE_INTERVAL: u32 = 5;
+/// Points per wave number for getting through a wave without being hit.
+const NO_DAMAGE_BONUS: u32 = 100;
+
+/// Everything that lives across scenes: the world, the current run and the high score.
+pub struct Game {
+    pub world: World,
+    pub ship: Entity,
+    pub sheets: SpriteSheets,
+    pub settings: Settings,
+    pub balance: Balance,
+    /// The ship survives everything, for testing.
+    pub god: bool,
+    /// Multiplier for the speed of the simulation, for testing.
+    pub time_scale: f32,
+    pub score: u32,
+    /// Best score on each difficulty, see `Difficulty::index`.
+    pub high_scores: [u32; Difficulty::ALL.len()],
+    pub got_high_score: bool,
+    pub combo: Combo,
+    pub run: RunStats,
+    /// Whether `run` has started and not been added to `lifetime` yet.
+    run_active: bool,
+    pub lifetime: LifetimeStats,
+    pub achievements: Achievements,
+    pub events: EventBus,
+    pub parallax: Parallax,
+    pub camera: GameCamera,
+    /// How the world was framed this frame.
+    pub view: View,
+    /// Strength of the chromatic aberration effect, kicked up by hits and fading back to 0.
+    pub aberration: f32,
+    /// Background the current stage of the run asks for.
+    pub starfield: StarfieldParams,
+    /// Current wave, counting from 1.
+    pub wave: u32,
+    /// Seconds since the current wave started.
+    wave_time: f32,
+    adaptive: AdaptiveDifficulty,
+    pub popups: ScorePopups,
+    /// Whether the ship was hit during the current wave, even if god mode saved it.
+    wave_damaged: bool,
+    /// Shots fired during the current wave.
+    wave_shots: u32,
+    scenery: Scenery,
+    /// Solid scenery on screen this tick, reused between ticks.
+    obstacles: Vec<Rect>,
+    collision_system: CollisionSystem,
+    /// Engine flame of each of `SHIP_THRUSTERS`, while it burns.
+    flames: [Option<Entity>; SHIP_THRUSTERS.len()],
+    /// Which way the ship is leaning: -1 left, 1 right, 0 level.
+    bank: f32,
+    last_shot_time: f64,
+    music_level: f32,
+    music_target: f32,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        let mut world = World::default();
+        let ship = prefabs::ship(&mut world, screen_center());
+        Game {
+            world,
+            ship,
+            sheets: SpriteSheets::new(),
+            settings: Settings::default(),
+            balance: Balance::default(),
+            god: false,
+            time_scale: 1.0,
+            score: 0,
+            high_scores: [0; Difficulty::ALL.len()],
+            got_high_score: false,
+            combo: Combo::default(),
+            run: RunStats::default(),
+            run_active: false,
+            lifetime: LifetimeStats::load(),
+            achievements: Achievements::load(),
+            events: EventBus::default(),
+            parallax: Parallax::default(),
+            camera: GameCamera::default(),
+            view: View::NEUTRAL,
+            aberration: 0.0,
+            starfield: StarfieldParams::CRUISE,
+            wave: 1,
+            wave_time: 0.0,
+            adaptive: AdaptiveDifficulty::default(),
+            popups: ScorePopups::default(),
+            wave_damaged: false,
+            wave_shots: 0,
+            scenery: Scenery::default(),
+            obstacles: vec![],
+            collision_system: CollisionSystem::new(COLLISION_CELL_SIZE),
+            flames: [None; SHIP_THRUSTERS.len()],
+            bank: 0.0,
+            last_shot_time: get_time(),
+            music_level: 0.0,
+            music_target: 0.0,
+        }
+    }
+
+    /// Throws away the previous run and puts a fresh ship in the middle of the screen.
+    pub fn new_run(&mut self) {
+        self.world.clear();
+        self.flames = [None; SHIP_THRUSTERS.len()];
+        self.ship = prefabs::ship(&mut self.world, screen_center());
+        self.got_high_score = false;
+        self.score = 0;
+        self.combo.reset();
+        self.run = RunStats::default();
+        self.run_active = true;
+        self.popups.clear();
+        self.aberration = 0.0;
+        self.parallax.reset();
+        self.camera.reset();
+        self.starfield = StarfieldParams::CRUISE;
+        self.start_wave(1);
+    }
+
+    /// Adds to the score, and to the high score for the difficulty if it's beaten.
+    pub fn add_score(&mut self, points: u32) {
+        self.score += points;
+        let high_score = &mut self.high_scores[self.settings.difficulty.index()];
+        if self.score > *high_score {
+            self.got_high_score = true;
+            *high_score = self.score;
+        }
+    }
+
+    /// Whether a run has started and hasn't been added to the lifetime stats yet.
+    pub fn in_run(&self) -> bool {
+        self.run_active
+    }
+
+    /// Adds the run that just ended to the lifetime stats, once, whichever way it ended.
+    pub fn end_run(&mut self) {
+        if std::mem::take(&mut self.run_active) {
+            self.lifetime.record(&self.run);
+        }
+    }
+
+    /// Sets the music volume for the current scene, before the player's music setting applies.
+    /// The volume eases towards it in `update_music`.
+    pub fn set_music_level(&mut self, level: f32) {
+        self.music_target = level;
+    }
+
+    /// Eases the music towards the current scene's level. `duck` lowers it further while a
+    /// screen transition covers the screen, so scene changes crossfade rather than jump.
+    pub fn update_music(&mut self, resources: &Resources, delta_time: f32, duck: f32) {
+        let step = MUSIC_FADE_SPEED * delta_time;
+        self.music_level += (self.music_target - self.music_level).clamp(-step, step);
+        set_sound_volume(
+            &resources.theme_music,
+            self.music_level * self.settings.music_volume * (1.0 - duck),
+        );
+    }
+
+    /// Advances the run by one frame. Returns true if the ship was destroyed.
+    pub fn update(&mut self, resources: &Resources, delta_time: f32) -> bool {
+        let delta_time = delta_time * self.time_scale;
+        self.update_wave(delta_time);
+        let boss_wave = self.is_boss_wave();
+
+        let balance = &self.balance;
+        let difficulty = self.settings.difficulty;
+        // Adaptive difficulty mostly changes how many enemies come, and their speed a little
+        let intensity = if self.settings.adaptive_difficulty {
+            self.adaptive.intensity()
+        } else {
+            1.0
+        };
+        let spawn_chance = balance.spawn_chance * difficulty.spawn_rate() * intensity;
+        let speed_scale = difficulty.enemy_speed() * (1.0 + (intensity - 1.0) / 2.0);
+        let max_bullets = (balance.max_bullets as f32 * difficulty.bullet_density()).round();
+        let max_bullets = (max_bullets as usize).max(1);
+
+        let world = &mut self.world;
+        if self.wave_time < WAVE_LENGTH && rand::gen_range(0.0, 1.0) < spawn_chance {
+            let size = if boss_wave {
+                rand::gen_range(48.0, 64.0)
+            } else {
+                rand::gen_range(16.0, 64.0)
+            };
+            let pos = vec2(
+                rand::gen_range(size / 2.0, canvas::WIDTH - size / 2.0),
+                -size,
+            );
+            let speed = rand::gen_range(balance.enemy_speed_min, balance.enemy_speed_max);
+            let health = difficulty.enemy_health();
+            prefabs::enemy(world, resources, pos, size, speed * speed_scale, health);
+        }
+
+        let mut ship_velocity = Vec2::ZERO;
+        if is_key_down(KeyCode::Right) {
+            ship_velocity.x += balance.movement_speed;
+        }
+        if is_key_down(KeyCode::Left) {
+            ship_velocity.x -= balance.movement_speed;
+        }
+        if is_key_down(KeyCode::Down) {
+            ship_velocity.y += balance.movement_speed;
+        }
+        if is_key_down(KeyCode::Up) {
+            ship_velocity.y -= balance.movement_speed;
+        }
+
+        if let Some(velocity) = world.velocities.get_mut(self.ship) {
+            velocity.0 = ship_velocity;
+        }
+        self.bank = if ship_velocity.x.abs() < 1.0 {
+            0.0
+        } else {
+            ship_velocity.x.signum()
+        };
+        self.sheets.ship.set_animation(match self.bank {
+            bank if bank < 0.0 => 1,
+            bank if bank > 0.0 => 2,
+            _ => 0,
+        });
+        self.parallax.update(ship_velocity, delta_time);
+
+        let ship_pos = world.transforms.get(self.ship).unwrap().pos;
+        let bullet_count = world
+            .colliders
+            .iter()
+            .filter(|(_, collider)| collider.layer == Layer::PlayerShot)
+            .count();
+        if self.last_shot_time + balance.bullet_cooldown < get_time()
+            && bullet_count < max_bullets
+            && is_key_pressed(KeyCode::Space)
+        {
+            prefabs::projectile(
+                world,
+                ship_pos - vec2(0.0, 24.0),
+                balance.movement_speed * 2.0,
+            );
+            self.last_shot_time = get_time();
+            self.events.emit(GameEvent::ShotFired);
+        }
+
+        systems::movement(world, delta_time);
+        if let Some(transform) = world.transforms.get_mut(self.ship) {
+            transform.pos = transform
+                .pos
+                .clamp(Vec2::ZERO, vec2(canvas::WIDTH, canvas::HEIGHT));
+        }
+        self.sheets.update();
+
+        self.scenery
+            .obstacles(&resources.levels, &self.parallax, &mut self.obstacles);
+        let contacts = self
+            .collision_system
+            .run(world, resources, &self.sheets, &self.obstacles);
+        let ship_hit = systems::damage(world, contacts, &mut self.events);
+        // Only once collisions are done, so shots that hit something on their way off screen
+        // still count
+        systems::cull(world, canvas::HEIGHT);
+        let ship_destroyed = ship_hit.is_some() && !self.god;
+        if let Some(cause) = ship_hit {
+            self.events.emit(GameEvent::PlayerHit {
+                cause,
+                destroyed: ship_destroyed,
+            });
+        }
+        self.run.time_survived += delta_time;
+        self.combo.update(delta_time);
+        self.adaptive.update(delta_time);
+        // Quiet stretches don't send events, but time still counts towards some achievements
+        achievements::check_run(self, resources);
+        ship_destroyed
+    }
+
+    /// Solid scenery the ship was checked against this tick.
+    #[cfg(debug_assertions)]
+    pub fn obstacles(&self) -> &[Rect] {
+        &self.obstacles
+    }
+
+    pub fn is_boss_wave(&self) -> bool {
+        self.wave.is_multiple_of(BOSS_WAVE_INTERVAL)
+    }
+
+    /// Jumps straight to the start of `wave`.
+    pub fn start_wave(&mut self, wave: u32) {
+        self.wave = wave.max(1);
+        self.wave_time = 0.0;
+        self.wave_damaged = false;
+        self.wave_shots = 0;
+        self.scenery.start(self.wave as usize - 1);
+        self.events.emit(GameEvent::WaveStarted);
+    }
+
+    /// Moves on to the next wave once the break after this one is over, and sets the
+    /// background to match: faster during breaks, streaking into the next wave's level, and red
+    /// for boss waves.
+    fn update_wave(&mut self, delta_time: f32) {
+        let spawning = self.wave_time < WAVE_LENGTH;
+        self.wave_time += delta_time;
+        if spawning && self.wave_time >= WAVE_LENGTH {
+            if !self.wave_damaged {
+                let bonus = NO_DAMAGE_BONUS * self.wave;
+                self.add_score(bonus);
+                self.popups.spawn(screen_center(), bonus, 1);
+            }
+            if self.wave_shots == 0 {
+                self.run.quiet_waves += 1;
+            }
+        }
+        if self.wave_time >= WAVE_LENGTH + WAVE_BREAK {
+            self.start_wave(self.wave + 1);
+        }
+        self.scenery.update(delta_time);
+
+        self.starfield = StarfieldParams::CRUISE;
+        if self.wave_time >= WAVE_LENGTH {
+            self.starfield.speed = 4.0;
+            self.starfield.layers = 6.0;
+        }
+        if self.wave_time >= WAVE_LENGTH + WAVE_BREAK - LEVEL_WARP_TIME {
+            self.starfield.warp = 1.0;
+        }
+        if self.is_boss_wave() {
+            self.starfield.tint = vec3(0.4, 0.12, 0.1);
+        }
+    }
+
+    /// Draws the world through the camera, then the score and achievement notifications. The
+    /// engine flames only burn while `playing`. Effects move on by `delta_time`, which is zero
+    /// while the simulation is held, e.g. by the debug overlay. A held frame keeps its camera
+    /// shake and popups but hides particles, which can't be drawn without stepping them.
+    pub fn draw(&mut self, resources: &Resources, canvas: &Canvas, playing: bool, delta_time: f32) {
+        self.aberration = (self.aberration - ABERRATION_DECAY * delta_time).max(0.0);
+        let focus = if self.settings.camera_follow {
+            self.world
+                .transforms
+                .get(self.ship)
+                .map(|transform| transform.pos)
+        } else {
+            None
+        };
+        self.camera.update(focus, screen_center(), delta_time);
+        // The shake is rolled fresh for every view, so a frozen frame keeps the last one
+        if delta_time > 0.0 {
+            self.view = self.camera.view(self.settings.screen_shake);
+        }
+        canvas.set_view(self.view);
+
+        self.update_thrusters(resources, playing);
+        self.scenery.draw(&resources.levels, &self.parallax);
+        systems::follow_anchors(&mut self.world);
+        systems::render(&mut self.world, resources, &self.sheets, delta_time);
+        let world = &self.world;
+        for flame in &mut self.flames {
+            *flame = flame.filter(|&flame| world.is_alive(flame));
+        }
+
+        self.popups.draw(delta_time);
+        canvas.set_view(View::NEUTRAL);
+
+        draw_text(
+            format!("Score: {}", self.score).as_str(),
+            10.,
+            35.,
+            25.,
+            WHITE,
+        );
+        if self.combo.multiplier() > 1 && playing {
+            draw_text(
+                format!("Combo x{}", self.combo.multiplier()).as_str(),
+                10.,
+                60.,
+                25.,
+                GOLD,
+            );
+        }
+        let highscore_string = format!(
+            "High score ({}): {}",
+            self.settings.difficulty.name(),
+            self.high_scores[self.settings.difficulty.index()]
+        );
+        let highscore_text = highscore_string.as_str();
+        let td = measure_text(highscore_text, None, 25, 1.0);
+        draw_text(
+            highscore_text,
+            canvas::WIDTH - td.width - 10.0,
+            35.,
+            25.,
+            WHITE,
+        );
+
+        let banner = self.scenery.banner(&resources.levels);
+        if let Some(name) = banner.filter(|_| playing) {
+            draw_text_centered(name, -2.0);
+        }
+        self.achievements.draw_toasts(delta_time);
+    }
+
+    /// Lights the engine flames while `playing` and lets them die out otherwise. Flames burn
+    /// longer and denser the faster the ship flies forward, and while banking the thrusters
+    /// close in and the outer one pushes harder.
+    fn update_thrusters(&mut self, resources: &Resources, playing: bool) {
+        let velocity = self
+            .world
+            .velocities
+            .get(self.ship)
+            .map_or(Vec2::ZERO, |velocity| velocity.0);
+        // 1 at full speed forward, 0.5 hovering, 0 going backwards
+        let thrust = (0.5 - 0.5 * velocity.y / self.balance.movement_speed).clamp(0.0, 1.0);
+        let preset = resources.particles.get(ParticleEffect::EngineFlame);
+
+        for (flame, base_offset) in self.flames.iter_mut().zip(SHIP_THRUSTERS) {
+            let offset = if self.bank == 0.0 {
+                base_offset
+            } else {
+                vec2(base_offset.x * BANK_SQUASH, base_offset.y)
+            };
+            if playing && flame.is_none() {
+                *flame = prefabs::flame(&mut self.world, resources, self.ship, offset);
+            }
+            let Some(flame) = *flame else {
+                continue;
+            };
+
+            if let Some(anchor) = self.world.anchors.get_mut(flame) {
+                anchor.offset = offset;
+            }
+            if let Some(flame) = self.world.emitters.get_mut(flame) {
+                let outer = -self.bank * base_offset.x.signum();
+                let thrust = (thrust + outer * BANK_THRUST).clamp(0.0, 1.0);
+                let config = &mut flame.emitter.config;
+                config.one_shot = !playing;
+                config.amount = ((flame.reserved as f32 * (0.3 + 0.7 * thrust)) as u32).max(1);
+                config.initial_velocity = preset.velocity * (0.4 + 1.2 * thrust);
+            }
+        }
+    }
+}
+
+/// Run bookkeeping the game keeps for itself: wave tracking and adaptive difficulty.
+pub fn register_subscribers(events: &mut EventBus) {
+    events.subscribe(|game, _, event| match *event {
+        GameEvent::ShotFired => game.wave_shots += 1,
+        GameEvent::EnemyKilled { .. } => game.adaptive.kill(),
+        GameEvent::Grazed => game.adaptive.graze(),
+        GameEvent::PlayerHit { destroyed, .. } => {
+            // Hits count against the wave even if god mode saved the ship
+            game.wave_damaged = true;
+            if destroyed {
+                game.adaptive.death();
+            }
+        }
+        _ => {}
+    });
+}
+
+#[cfg(debug_assertions)]
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(Command {
+        name: "god",
+        usage: "[on|off]",
+        completions: &["on", "off"],
+        run: |game, _, args| {
+            game.god = match args.first() {
+                Some(&"on") => true,
+                Some(&"off") => false,
+                Some(arg) => return Err(format!("expected on or off, not {}", arg)),
+                None => !game.god,
+            };
+            Ok(format!("god mode {}", if game.god { "on" } else { "off" }))
+        },
+    });
+    commands.register(Command {
+        name: "wave",
+        usage: "<number>",
+        completions: &[],
+        run: |game, _, args| {
+            game.start_wave(arg(args, 0, "wave")?);
+            Ok(format!("wave {}", game.wave))
+        },
+    });
+    commands.register(Command {
+        name: "timescale",
+        usage: "<multiplier>",
+        completions: &[],
+        run: |game, _, args| {
+            let scale: f32 = arg(args, 0, "multiplier")?;
+            if !(0.0..=10.0).contains(&scale) {
+                return Err("timescale goes from 0 to 10".to_string());
+            }
+            game.time_scale = scale;
+            Ok(format!("timescale {}", scale))
+        },
+    });
+    commands.register(Command {
+        name: "give",
+        usage: "score <points>",
+        completions: &["score"],
+        run: |game, _, args| match args.first() {
+            Some(&"score") => {
+                let points: u32 = arg(args, 1, "points")?;
+                game.add_score(points);
+                Ok(format!("score {}", game.score))
+            }
+            // Power-ups don't do anything yet, so there are none to give
+            Some(item) => Err(format!("can't give {}, only score <points>", item)),
+            None => Err("missing item, only score <points>".to_string()),
+        },
+    });
+}
+
+fn screen_center() -> Vec2 {
+    vec2(canvas::WIDTH / 2.0, canvas::HEIGHT / 2.0)
+}
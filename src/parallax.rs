@@ -0,0 +1,30 @@
+use macroquad::math::Vec2;
+
+/// How quickly the view drifts back to neutral, per second.
+const RETURN_RATE: f32 = 1.5;
+
+/// Camera sway for background layers. The ship's movement pushes the view along, and it
+/// eases back to neutral once the ship stops, so the offset stays bounded however long the
+/// player holds a direction.
+#[derive(Default)]
+pub struct Parallax {
+    /// Distance in pixels the view has been pushed.
+    offset: Vec2,
+}
+
+impl Parallax {
+    pub fn update(&mut self, velocity: Vec2, delta_time: f32) {
+        self.offset += velocity * delta_time;
+        self.offset *= (-RETURN_RATE * delta_time).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.offset = Vec2::ZERO;
+    }
+
+    /// How far a layer at `depth` should shift, in pixels. Depth 0 is infinitely far away
+    /// and doesn't move; depth 1 moves as much as the foreground.
+    pub fn layer_offset(&self, depth: f32) -> Vec2 {
+        -self.offset * depth
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use macroquad_particles::{AtlasConfig, BlendMode, ColorCurve, EmitterConfig};
+use nanoserde::DeRon;
+
+use crate::{resources::load_texture_from_file, ASSETS_FOLDER};
+
+const PRESETS_PATH: &str = "particles.ron";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ParticleEffect {
+    ExplosionSmall,
+    ExplosionBig,
+    EngineFlame,
+    HitSpark,
+    PickupGlow,
+    SmokeTrail,
+}
+
+impl ParticleEffect {
+    pub const ALL: [ParticleEffect; 6] = [
+        ParticleEffect::ExplosionSmall,
+        ParticleEffect::ExplosionBig,
+        ParticleEffect::EngineFlame,
+        ParticleEffect::HitSpark,
+        ParticleEffect::PickupGlow,
+        ParticleEffect::SmokeTrail,
+    ];
+
+    /// Effects that are the first to go when there are too many particles.
+    pub fn is_cosmetic(self) -> bool {
+        matches!(
+            self,
+            ParticleEffect::HitSpark | ParticleEffect::PickupGlow | ParticleEffect::SmokeTrail
+        )
+    }
+
+    /// Key of the effect's preset in the presets file.
+    pub fn name(self) -> &'static str {
+        match self {
+            ParticleEffect::ExplosionSmall => "explosion_small",
+            ParticleEffect::ExplosionBig => "explosion_big",
+            ParticleEffect::EngineFlame => "engine_flame",
+            ParticleEffect::HitSpark => "hit_spark",
+            ParticleEffect::PickupGlow => "pickup_glow",
+            ParticleEffect::SmokeTrail => "smoke_trail",
+        }
+    }
+}
+
+pub use preset::ParticlePreset;
+
+// Allows the same derive lint as `balance::file`
+#[allow(clippy::question_mark)]
+mod preset {
+    use nanoserde::{DeRon, SerRon};
+
+    /// One entry of the presets file. Mirrors the parts of `EmitterConfig` we use, in units that
+    /// are easier to tune by hand.
+    #[derive(Clone, PartialEq, DeRon, SerRon)]
+    pub struct ParticlePreset {
+        pub amount: u32,
+        #[nserde(default)]
+        pub one_shot: bool,
+        pub lifetime: f32,
+        #[nserde(default)]
+        pub lifetime_randomness: f32,
+        #[nserde(default)]
+        pub explosiveness: f32,
+        /// Emission direction, with y pointing down the screen.
+        pub direction: (f32, f32),
+        /// Random spread around `direction`, in degrees.
+        #[nserde(default)]
+        pub spread: f32,
+        pub velocity: f32,
+        #[nserde(default)]
+        pub velocity_randomness: f32,
+        #[nserde(default)]
+        pub linear_accel: f32,
+        #[nserde(default)]
+        pub gravity: (f32, f32),
+        pub size: f32,
+        #[nserde(default)]
+        pub size_randomness: f32,
+        /// Start, middle and end colour over each particle's life, as RGBA from 0 to 1.
+        #[nserde(default)]
+        pub colors: Option<([f32; 4], [f32; 4], [f32; 4])>,
+        #[nserde(default)]
+        pub additive: bool,
+        /// Image in the assets folder. Particles are plain squares without one.
+        #[nserde(default)]
+        pub texture: Option<String>,
+        /// Columns and rows of animation frames in `texture`.
+        #[nserde(default)]
+        pub atlas: Option<(u16, u16)>,
+    }
+}
+
+/// Named particle effects loaded from `assets/particles.ron`.
+#[derive(Clone)]
+pub struct ParticlePresets {
+    presets: Vec<ParticlePreset>,
+    textures: HashMap<String, Texture2D>,
+}
+
+impl ParticlePresets {
+    pub async fn load() -> Result<Self, macroquad::Error> {
+        let presets = parse(&load_string(PRESETS_PATH).await?).map_err(|err| {
+            error!("{}: {}", PRESETS_PATH, err);
+            macroquad::Error::UnknownError("invalid particle presets")
+        })?;
+
+        let mut textures = HashMap::new();
+        for path in presets.iter().filter_map(|preset| preset.texture.as_ref()) {
+            if !textures.contains_key(path) {
+                textures.insert(path.clone(), load_texture_from_file(path).await?);
+            }
+        }
+
+        Ok(ParticlePresets { presets, textures })
+    }
+
+    /// Reads the presets file again, for tuning without restarting. Textures can't be added
+    /// this way, only ones loaded at startup can be used.
+    pub fn reload(&mut self) -> Result<(), String> {
+        let path = format!("{}/{}", ASSETS_FOLDER, PRESETS_PATH);
+        let text = std::fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?;
+        let presets = parse(&text)?;
+        let unknown_texture = presets
+            .iter()
+            .filter_map(|preset| preset.texture.as_ref())
+            .find(|texture| !self.textures.contains_key(*texture));
+        if let Some(texture) = unknown_texture {
+            return Err(format!("{} wasn't loaded at startup", texture));
+        }
+
+        self.presets = presets;
+        Ok(())
+    }
+
+    pub fn get(&self, effect: ParticleEffect) -> &ParticlePreset {
+        &self.presets[effect as usize]
+    }
+
+    pub fn get_mut(&mut self, effect: ParticleEffect) -> &mut ParticlePreset {
+        &mut self.presets[effect as usize]
+    }
+
+    pub fn config(&self, effect: ParticleEffect) -> EmitterConfig {
+        let preset = self.get(effect);
+        EmitterConfig {
+            amount: preset.amount,
+            local_coords: false,
+            one_shot: preset.one_shot,
+            emitting: true,
+            lifetime: preset.lifetime,
+            lifetime_randomness: preset.lifetime_randomness,
+            explosiveness: preset.explosiveness,
+            initial_direction: vec2(preset.direction.0, preset.direction.1).normalize_or_zero(),
+            initial_direction_spread: preset.spread.to_radians(),
+            initial_velocity: preset.velocity,
+            initial_velocity_randomness: preset.velocity_randomness,
+            linear_accel: preset.linear_accel,
+            gravity: vec2(preset.gravity.0, preset.gravity.1),
+            size: preset.size,
+            size_randomness: preset.size_randomness,
+            colors_curve: preset
+                .colors
+                .map_or_else(ColorCurve::default, |(start, mid, end)| ColorCurve {
+                    start: Color::from(start),
+                    mid: Color::from(mid),
+                    end: Color::from(end),
+                }),
+            blend_mode: if preset.additive {
+                BlendMode::Additive
+            } else {
+                BlendMode::Alpha
+            },
+            texture: preset
+                .texture
+                .as_ref()
+                .and_then(|path| self.textures.get(path).cloned()),
+            atlas: preset
+                .atlas
+                .map(|(columns, rows)| AtlasConfig::new(columns, rows, 0..)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Picks out a preset for every effect, in `ParticleEffect` order.
+fn parse(text: &str) -> Result<Vec<ParticlePreset>, String> {
+    let mut file: HashMap<String, ParticlePreset> =
+        DeRon::deserialize_ron(text).map_err(|err| err.to_string())?;
+    let presets = ParticleEffect::ALL
+        .iter()
+        .map(|effect| {
+            file.remove(effect.name())
+                .ok_or_else(|| format!("missing preset {}", effect.name()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for name in file.keys() {
+        warn!("unknown particle preset {}", name);
+    }
+    Ok(presets)
+}
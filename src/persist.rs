@@ -0,0 +1,86 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use macroquad::logging::warn;
+use nanoserde::{DeRon, SerRon};
+
+/// Folder inside the user's data folder that saved files go in.
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_FOLDER: &str = "my-game";
+
+/// The user's data folder for the game, if the platform has one.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_dir() -> Option<PathBuf> {
+    let env = |name| std::env::var_os(name).map(PathBuf::from);
+    let base = env("XDG_DATA_HOME")
+        .or_else(|| env("APPDATA"))
+        .or_else(|| env("HOME").map(|home| home.join(".local").join("share")))?;
+    Some(base.join(SAVE_FOLDER))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(save_dir()?.join(name)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(name: &str, text: &str) -> Result<(), String> {
+    let dir = save_dir().ok_or("no data folder to save in")?;
+    let path = dir.join(name);
+    std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&path, text))
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// On the web, saved files live in the browser's local storage under their name. The
+/// functions are added to the wasm imports by the plugin in `index.html`.
+#[cfg(target_arch = "wasm32")]
+mod web {
+    unsafe extern "C" {
+        /// Length in bytes of the stored value, or -1 if there is none.
+        fn storage_len(key: *const u8, key_len: usize) -> i32;
+        /// Copies the stored value into `out`, which must be `storage_len` bytes.
+        fn storage_read(key: *const u8, key_len: usize, out: *mut u8, out_len: usize);
+        /// Returns 0 if the browser refused the value, e.g. because storage is full or off.
+        fn storage_write(key: *const u8, key_len: usize, value: *const u8, value_len: usize)
+            -> i32;
+    }
+
+    pub fn read(name: &str) -> Option<String> {
+        // SAFETY: the plugin only reads `key_len` bytes of `key` and writes `out_len` of `out`
+        unsafe {
+            let len = usize::try_from(storage_len(name.as_ptr(), name.len())).ok()?;
+            let mut bytes = vec![0; len];
+            storage_read(name.as_ptr(), name.len(), bytes.as_mut_ptr(), len);
+            String::from_utf8(bytes).ok()
+        }
+    }
+
+    pub fn write(name: &str, text: &str) -> Result<(), String> {
+        // SAFETY: the plugin only reads `key_len` bytes of `key` and `value_len` of `value`
+        let stored = unsafe { storage_write(name.as_ptr(), name.len(), text.as_ptr(), text.len()) };
+        if stored == 0 {
+            return Err("the browser didn't store it".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+use web::{read, write};
+
+/// Reads a saved file. None if nothing has been saved yet, or it can't be read.
+pub fn load<T: DeRon>(name: &str) -> Option<T> {
+    let text = read(name)?;
+    T::deserialize_ron(&text)
+        .map_err(|err| warn!("{}: {}", name, err))
+        .ok()
+}
+
+/// Writes a saved file. Failing to save isn't worth interrupting the game for, so errors are
+/// only logged.
+pub fn save<T: SerRon>(name: &str, value: &T) {
+    if let Err(err) = write(name, &value.serialize_ron()) {
+        warn!("Couldn't save {}: {}", name, err);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fmt, marker::PhantomData};
+
+/// Stable reference to a value in a `Pool`. A handle stays valid until its value is removed;
+/// after that it never matches a newer value that reuses the same slot.
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    /// Slot index, for storing data about the value in a side table.
+    pub fn index(self) -> usize {
+        self.index as usize
+    }
+}
+
+// Implemented by hand so that handles are Copy regardless of T
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Generational arena. Removed slots are recycled, so after the first few waves spawning and
+/// despawning entities doesn't allocate.
+pub struct Pool<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Pool<T> {
+    pub fn new() -> Self {
+        Pool {
+            slots: vec![],
+            free: vec![],
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        self.len += 1;
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].value = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+
+        Handle {
+            index,
+            generation: self.slots[index as usize].generation,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let slot = self.slots.get_mut(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.slots
+            .get(handle.index as usize)
+            .is_some_and(|slot| slot.generation == handle.generation && slot.value.is_some())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value.as_ref().map(|value| {
+                (
+                    Handle {
+                        index: index as u32,
+                        generation: slot.generation,
+                        _marker: PhantomData,
+                    },
+                    value,
+                )
+            })
+        })
+    }
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Pool::new()
+    }
+}
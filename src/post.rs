@@ -0,0 +1,138 @@
+use macroquad::prelude::*;
+
+use crate::{settings::Settings, shader};
+
+/// Below this the chromatic aberration pass is skipped entirely.
+const MIN_ABERRATION: f32 = 0.01;
+
+#[derive(Clone, Copy)]
+enum Pass {
+    Bloom,
+    Aberration,
+    Scanlines,
+    Crt,
+}
+
+impl Pass {
+    /// Passes in the order they're applied. The CRT curvature goes last so it bends
+    /// everything else with it.
+    const ALL: [Pass; 4] = [Pass::Bloom, Pass::Aberration, Pass::Scanlines, Pass::Crt];
+
+    fn fragment_shader(self) -> &'static str {
+        match self {
+            Pass::Bloom => shader::BLOOM_FRAGMENT_SHADER,
+            Pass::Aberration => shader::ABERRATION_FRAGMENT_SHADER,
+            Pass::Scanlines => shader::SCANLINES_FRAGMENT_SHADER,
+            Pass::Crt => shader::CRT_FRAGMENT_SHADER,
+        }
+    }
+
+    fn uniforms(self) -> Vec<UniformDesc> {
+        match self {
+            Pass::Bloom => vec![UniformDesc::new("texel", UniformType::Float2)],
+            Pass::Aberration => vec![UniformDesc::new("amount", UniformType::Float1)],
+            Pass::Scanlines => vec![UniformDesc::new("iResolution", UniformType::Float2)],
+            Pass::Crt => vec![],
+        }
+    }
+
+    fn is_enabled(self, settings: &Settings, aberration: f32) -> bool {
+        match self {
+            Pass::Bloom => settings.bloom,
+            Pass::Aberration => settings.chromatic_aberration && aberration > MIN_ABERRATION,
+            Pass::Scanlines => settings.scanlines,
+            Pass::Crt => settings.crt,
+        }
+    }
+}
+
+/// Chain of full-screen passes run over the finished canvas, ping-ponging between two
+/// targets the size of the canvas.
+pub struct PostProcess {
+    materials: Vec<Material>,
+    targets: Vec<RenderTarget>,
+}
+
+impl PostProcess {
+    pub fn new() -> Result<Self, macroquad::Error> {
+        let materials = Pass::ALL
+            .iter()
+            .map(|pass| {
+                load_material(
+                    ShaderSource::Glsl {
+                        vertex: shader::POST_VERTEX_SHADER,
+                        fragment: pass.fragment_shader(),
+                    },
+                    MaterialParams {
+                        uniforms: pass.uniforms(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PostProcess {
+            materials,
+            targets: vec![],
+        })
+    }
+
+    /// Runs the passes enabled in `settings` over `source` and returns the texture to show.
+    /// `aberration` is how strongly to split colours, from 0 to 1.
+    pub fn apply(&mut self, source: &Texture2D, settings: &Settings, aberration: f32) -> Texture2D {
+        let size = source.size();
+        if self
+            .targets
+            .first()
+            .is_none_or(|target| target.texture.size() != size)
+        {
+            self.targets = (0..2)
+                .map(|_| render_target(size.x as u32, size.y as u32))
+                .collect();
+        }
+        let filter = if settings.low_res {
+            FilterMode::Nearest
+        } else {
+            FilterMode::Linear
+        };
+
+        let mut input = source.clone();
+        let mut next = 0;
+        for (pass, material) in Pass::ALL.iter().zip(&self.materials) {
+            if !pass.is_enabled(settings, aberration) {
+                continue;
+            }
+            match pass {
+                Pass::Bloom => material.set_uniform("texel", vec2(1.0, 1.0) / size),
+                Pass::Aberration => material.set_uniform("amount", aberration),
+                Pass::Scanlines => material.set_uniform("iResolution", size),
+                Pass::Crt => {}
+            }
+
+            let target = &self.targets[next];
+            target.texture.set_filter(filter);
+            set_camera(&Camera2D {
+                target: size / 2.0,
+                zoom: vec2(2.0, 2.0) / size,
+                render_target: Some(target.clone()),
+                ..Default::default()
+            });
+            gl_use_material(material);
+            draw_texture_ex(
+                &input,
+                0.0,
+                0.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(size),
+                    ..Default::default()
+                },
+            );
+            gl_use_default_material();
+
+            input = target.texture.clone();
+            next = 1 - next;
+        }
+        input
+    }
+}
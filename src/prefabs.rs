@@ -0,0 +1,251 @@
+use macroquad::{
+    color::Color,
+    math::{vec2, Vec2},
+};
+
+#[cfg(debug_assertions)]
+use crate::console::{arg, Command, CommandRegistry};
+use crate::{
+    collision::Hitbox,
+    components::{
+        Anchor, Collider, ColliderShape, Cull, EnemyKind, Health, Layer, ScoreValue, Sprite,
+        SpriteKind, Transform, Velocity,
+    },
+    ecs::{Entity, World},
+    particles::ParticleEffect,
+    resources::Resources,
+    sprites::SpriteSheet,
+};
+
+/// How fast pickups drift down the screen.
+const PICKUP_SPEED: f32 = 80.0;
+
+// Draw order, back to front
+const ENEMY_Z: i32 = 0;
+const PROJECTILE_Z: i32 = 1;
+const PICKUP_Z: i32 = 2;
+const FLAME_Z: i32 = 3;
+const SHIP_Z: i32 = 4;
+const BURST_Z: i32 = 5;
+
+pub fn ship(world: &mut World, pos: Vec2) -> Entity {
+    let entity = world.spawn();
+    world
+        .transforms
+        .insert(entity, Transform::new(pos, vec2(32.0, 48.0)));
+    world.velocities.insert(entity, Velocity(Vec2::ZERO));
+    world.sprites.insert(
+        entity,
+        Sprite {
+            kind: SpriteKind::Sheet(SpriteSheet::Ship),
+            z: SHIP_Z,
+        },
+    );
+    world.colliders.insert(
+        entity,
+        Collider {
+            layer: Layer::Player,
+            shape: ColliderShape::Frames(SpriteSheet::Ship),
+        },
+    );
+    entity
+}
+
+/// Big enemies trail smoke. `health` is the number of hits it takes to shoot one down.
+pub fn enemy(
+    world: &mut World,
+    resources: &Resources,
+    pos: Vec2,
+    size: f32,
+    speed: f32,
+    health: i32,
+) -> Entity {
+    let kind = EnemyKind::from_size(size);
+    let sheet = SpriteSheet::Enemy(kind);
+    let entity = world.spawn();
+    world
+        .transforms
+        .insert(entity, Transform::new(pos, vec2(size, size)));
+    world.velocities.insert(entity, Velocity(vec2(0.0, speed)));
+    world.culls.insert(entity, Cull { margin: size });
+    world.sprites.insert(
+        entity,
+        Sprite {
+            kind: SpriteKind::Sheet(sheet),
+            z: ENEMY_Z,
+        },
+    );
+    world.colliders.insert(
+        entity,
+        Collider {
+            layer: Layer::Enemy,
+            shape: ColliderShape::Frames(sheet),
+        },
+    );
+    world.healths.insert(entity, Health(health));
+    world.score_values.insert(
+        entity,
+        ScoreValue {
+            points: size.round() as u32,
+        },
+    );
+    if kind == EnemyKind::Big {
+        trail(
+            world,
+            resources,
+            entity,
+            Vec2::ZERO,
+            ParticleEffect::SmokeTrail,
+            ENEMY_Z,
+        );
+    }
+    entity
+}
+
+pub fn projectile(world: &mut World, pos: Vec2, speed: f32) -> Entity {
+    let size = 32.0;
+    let entity = world.spawn();
+    world
+        .transforms
+        .insert(entity, Transform::new(pos, vec2(size, size)));
+    world.velocities.insert(entity, Velocity(vec2(0.0, -speed)));
+    world.culls.insert(entity, Cull { margin: size / 2.0 });
+    world.sprites.insert(
+        entity,
+        Sprite {
+            kind: SpriteKind::Sheet(SpriteSheet::Bullet),
+            z: PROJECTILE_Z,
+        },
+    );
+    world.colliders.insert(
+        entity,
+        Collider {
+            layer: Layer::PlayerShot,
+            shape: ColliderShape::Frames(SpriteSheet::Bullet),
+        },
+    );
+    entity
+}
+
+/// Nothing in a run drops these yet, so outside debug builds, where the console can spawn
+/// them, they are never used.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub fn pickup(world: &mut World, resources: &Resources, pos: Vec2, color: Color) -> Entity {
+    let radius = 8.0;
+    let entity = world.spawn();
+    world
+        .transforms
+        .insert(entity, Transform::new(pos, vec2(radius, radius) * 2.0));
+    world
+        .velocities
+        .insert(entity, Velocity(vec2(0.0, PICKUP_SPEED)));
+    world.culls.insert(entity, Cull { margin: radius });
+    world.sprites.insert(
+        entity,
+        Sprite {
+            kind: SpriteKind::Orb(color),
+            z: PICKUP_Z,
+        },
+    );
+    world.colliders.insert(
+        entity,
+        Collider {
+            layer: Layer::Pickup,
+            shape: ColliderShape::Fixed(Hitbox::circle(0.5)),
+        },
+    );
+    trail(
+        world,
+        resources,
+        entity,
+        Vec2::ZERO,
+        ParticleEffect::PickupGlow,
+        PICKUP_Z,
+    );
+    entity
+}
+
+/// One-off particle effect at `pos`, despawned once its particles have died out. Nothing is
+/// spawned if the particle budget is used up.
+pub fn burst(
+    world: &mut World,
+    resources: &Resources,
+    pos: Vec2,
+    effect: ParticleEffect,
+) -> Option<Entity> {
+    let emitter = world.effects.spawn(&resources.particles, effect, BURST_Z)?;
+    let entity = world.spawn();
+    world
+        .transforms
+        .insert(entity, Transform::new(pos, Vec2::ZERO));
+    world.emitters.insert(entity, emitter);
+    Some(entity)
+}
+
+/// Particle effect that follows `target` around at `offset`, and winds down once it's gone.
+pub fn trail(
+    world: &mut World,
+    resources: &Resources,
+    target: Entity,
+    offset: Vec2,
+    effect: ParticleEffect,
+    z: i32,
+) -> Option<Entity> {
+    let emitter = world.effects.spawn(&resources.particles, effect, z)?;
+    let pos = world
+        .transforms
+        .get(target)
+        .map_or(Vec2::ZERO, |transform| transform.pos + offset);
+    let entity = world.spawn();
+    world
+        .transforms
+        .insert(entity, Transform::new(pos, Vec2::ZERO));
+    world.anchors.insert(entity, Anchor { target, offset });
+    world.emitters.insert(entity, emitter);
+    Some(entity)
+}
+
+/// Engine flame that follows `ship` around at `offset` from its centre.
+pub fn flame(
+    world: &mut World,
+    resources: &Resources,
+    ship: Entity,
+    offset: Vec2,
+) -> Option<Entity> {
+    trail(
+        world,
+        resources,
+        ship,
+        offset,
+        ParticleEffect::EngineFlame,
+        FLAME_Z,
+    )
+}
+
+#[cfg(debug_assertions)]
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(Command {
+        name: "spawn",
+        usage: "<enemy_small|enemy_medium|enemy_big|pickup> <x> <y>",
+        completions: &["enemy_small", "enemy_medium", "enemy_big", "pickup"],
+        run: |game, resources, args| {
+            let kind = arg::<String>(args, 0, "kind")?;
+            let pos = vec2(arg(args, 1, "x")?, arg(args, 2, "y")?);
+            let size = match kind.as_str() {
+                "enemy_small" => 24.0,
+                "enemy_medium" => 40.0,
+                "enemy_big" => 56.0,
+                "pickup" => {
+                    pickup(&mut game.world, resources, pos, macroquad::color::GOLD);
+                    return Ok("spawned pickup".to_string());
+                }
+                _ => return Err(format!("can't spawn {}", kind)),
+            };
+            let balance = &game.balance;
+            let speed = (balance.enemy_speed_min + balance.enemy_speed_max) / 2.0;
+            let health = game.settings.difficulty.enemy_health();
+            enemy(&mut game.world, resources, pos, size, speed, health);
+            Ok(format!("spawned {}", kind))
+        },
+    });
+}
@@ -1,22 +1,39 @@
+use crate::achievements::Achievement;
+use crate::collision::FrameHitboxes;
+use crate::particles::ParticlePresets;
+use crate::scenery::Level;
 use macroquad::{
     audio::{load_sound, Sound},
     color::{BLACK, WHITE},
     file::load_file,
     math::RectOffset,
     prelude::{collections::storage, coroutines::start_coroutine},
-    texture::{build_textures_atlas, load_image, load_texture, FilterMode, Texture2D},
+    texture::{build_textures_atlas, load_image, load_texture, FilterMode, Image, Texture2D},
     time::get_time,
     ui::{root_ui, Skin, StyleBuilder},
     window::{clear_background, next_frame},
 };
 
+/// Size of the ship's graze area relative to its hull. Enemies inside it but clear of the
+/// hull count as close calls.
+const SHIP_GRAZE_SCALE: f32 = 1.6;
+
+/// Level files in the order they're played, looping back to the first after the last.
+const LEVELS: [&str; 2] = ["levels/asteroid-belt.ron", "levels/station.ron"];
+
 pub struct Resources {
     pub ship_texture: Texture2D,
     pub bullet_texture: Texture2D,
-    pub explosion_texture: Texture2D,
     pub enemy_small_texture: Texture2D,
     pub enemy_medium_texture: Texture2D,
     pub enemy_big_texture: Texture2D,
+    pub ship_hitboxes: FrameHitboxes,
+    pub ship_graze_hitboxes: FrameHitboxes,
+    pub bullet_hitboxes: FrameHitboxes,
+    pub enemy_hitboxes: [FrameHitboxes; 3],
+    pub levels: Vec<Level>,
+    pub particles: ParticlePresets,
+    pub achievements: Vec<Achievement>,
     pub theme_music: Sound,
     pub sound_explosion: Sound,
     pub sound_laser: Sound,
@@ -25,14 +42,32 @@ pub struct Resources {
 
 impl Resources {
     pub async fn new() -> Result<Self, macroquad::Error> {
-        let ship_texture = load_texture_from_file("ship.png").await?;
-        let bullet_texture = load_texture_from_file("laser-bolts.png").await?;
-        let explosion_texture = load_texture_from_file("explosion.png").await?;
-        let enemy_small_texture = load_texture_from_file("enemy-small.png").await?;
-        let enemy_medium_texture = load_texture_from_file("enemy-medium.png").await?;
-        let enemy_big_texture = load_texture_from_file("enemy-big.png").await?;
+        let (ship_texture, ship_image) = load_texture_and_image("ship.png").await?;
+        let (bullet_texture, bullet_image) = load_texture_and_image("laser-bolts.png").await?;
+        let (enemy_small_texture, enemy_small_image) =
+            load_texture_and_image("enemy-small.png").await?;
+        let (enemy_medium_texture, enemy_medium_image) =
+            load_texture_and_image("enemy-medium.png").await?;
+        let (enemy_big_texture, enemy_big_image) = load_texture_and_image("enemy-big.png").await?;
         build_textures_atlas();
 
+        let ship_hitboxes = FrameHitboxes::from_alpha(&ship_image, 16, 24);
+        let ship_graze_hitboxes = ship_hitboxes.scaled(SHIP_GRAZE_SCALE);
+        let bullet_hitboxes = FrameHitboxes::from_alpha(&bullet_image, 16, 16);
+        let enemy_hitboxes = [
+            FrameHitboxes::from_alpha(&enemy_small_image, 17, 16),
+            FrameHitboxes::from_alpha(&enemy_medium_image, 32, 16),
+            FrameHitboxes::from_alpha(&enemy_big_image, 32, 32),
+        ];
+
+        let mut levels = vec![];
+        for path in LEVELS {
+            levels.push(Level::load(path).await?);
+        }
+
+        let particles = ParticlePresets::load().await?;
+        let achievements = Achievement::load_all().await?;
+
         let theme_music = load_sound("8bit-spaceshooter.ogg").await.unwrap();
         let sound_explosion = load_sound("explosion.wav").await.unwrap();
         let sound_laser = load_sound("laser.wav").await.unwrap();
@@ -69,10 +104,16 @@ impl Resources {
         Ok(Resources {
             ship_texture,
             bullet_texture,
-            explosion_texture,
             enemy_small_texture,
             enemy_medium_texture,
             enemy_big_texture,
+            ship_hitboxes,
+            ship_graze_hitboxes,
+            bullet_hitboxes,
+            enemy_hitboxes,
+            levels,
+            particles,
+            achievements,
             theme_music,
             sound_explosion,
             sound_laser,
@@ -102,7 +143,7 @@ impl Resources {
     }
 }
 
-async fn load_texture_from_file<P: std::fmt::Debug + AsRef<str>>(
+pub async fn load_texture_from_file<P: std::fmt::Debug + AsRef<str>>(
     path: P,
 ) -> Result<Texture2D, macroquad::Error> {
     let texture = load_texture(path.as_ref()).await?;
@@ -110,6 +151,15 @@ async fn load_texture_from_file<P: std::fmt::Debug + AsRef<str>>(
     Ok(texture)
 }
 
+async fn load_texture_and_image<P: std::fmt::Debug + AsRef<str>>(
+    path: P,
+) -> Result<(Texture2D, Image), macroquad::Error> {
+    let image = load_image(path.as_ref()).await?;
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    Ok((texture, image))
+}
+
 trait FontSetter: Sized {
     fn set_font(self, font: &[u8], size: u16) -> Result<Self, macroquad::Error>;
 }
@@ -0,0 +1,18 @@
+#version 100
+
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec2 iResolution;
+
+#define DARKNESS .3
+
+void main()
+{
+    vec3 col = texture2D(Texture, uv).rgb;
+    float line = mod(floor(uv.y * iResolution.y), 2.);
+
+    gl_FragColor = vec4(col * (1. - DARKNESS * line), 1.);
+}
@@ -0,0 +1,207 @@
+use macroquad::prelude::*;
+use nanoserde::DeRon;
+
+use crate::{canvas, parallax::Parallax, resources::load_texture_from_file};
+
+/// Seconds a new level's scenery takes to fade in.
+const FADE_IN: f32 = 1.5;
+/// Obstacle hitboxes are this much smaller than their tiles on each side, so clipping the
+/// corner of a rock is forgiven.
+const OBSTACLE_INSET: f32 = 3.0;
+/// Seconds the level name is shown when a level starts.
+const BANNER_TIME: f32 = 3.0;
+
+#[derive(DeRon)]
+struct LevelFile {
+    name: String,
+    /// Drawn back to front.
+    layers: Vec<LayerFile>,
+}
+
+#[derive(DeRon)]
+struct LayerFile {
+    /// Image in the assets folder, a single row of equally sized tiles.
+    sheet: String,
+    /// Number of tiles in the sheet.
+    columns: u32,
+    /// Size tiles are drawn at, which needn't match the sheet.
+    tile_width: f32,
+    tile_height: f32,
+    /// Rows of the map as they appear on screen. `.` is empty and a digit picks a tile.
+    rows: Vec<String>,
+    /// Pixels per second the layer moves down the screen.
+    scroll_speed: f32,
+    /// See `Parallax::layer_offset`.
+    depth: f32,
+    /// Whether tiles collide with the ship.
+    #[nserde(default)]
+    solid: bool,
+    /// Start over once the map has scrolled past, rather than leaving it behind.
+    #[nserde(default)]
+    repeat: bool,
+}
+
+struct SceneryLayer {
+    texture: Texture2D,
+    /// Size of one tile in the sheet.
+    source_size: Vec2,
+    tiles: Vec<Vec<Option<u32>>>,
+    tile_size: Vec2,
+    scroll_speed: f32,
+    depth: f32,
+    solid: bool,
+    repeat: bool,
+}
+
+impl SceneryLayer {
+    /// Calls `f` with the screen rectangle and sheet index of every visible tile.
+    fn visible_tiles(&self, time: f32, parallax: &Parallax, mut f: impl FnMut(Rect, u32)) {
+        let map_height = self.tiles.len() as f32 * self.tile_size.y;
+        if map_height <= 0.0 {
+            return;
+        }
+
+        // The map starts with its bottom edge at the top of the screen and scrolls down
+        let scrolled = time * self.scroll_speed;
+        let mut top = if self.repeat {
+            scrolled % map_height - map_height
+        } else {
+            scrolled - map_height
+        };
+        let offset = parallax.layer_offset(self.depth);
+        while top < canvas::HEIGHT {
+            for (row, tiles) in self.tiles.iter().enumerate() {
+                let y = top + row as f32 * self.tile_size.y + offset.y;
+                if y + self.tile_size.y < 0.0 || y > canvas::HEIGHT {
+                    continue;
+                }
+                for (column, tile) in tiles.iter().enumerate() {
+                    if let Some(tile) = *tile {
+                        let x = column as f32 * self.tile_size.x + offset.x;
+                        f(Rect::new(x, y, self.tile_size.x, self.tile_size.y), tile);
+                    }
+                }
+            }
+            if !self.repeat {
+                break;
+            }
+            top += map_height;
+        }
+    }
+}
+
+/// Scrolling backgrounds and obstacles for one level, loaded from a RON file in
+/// `assets/levels`.
+pub struct Level {
+    name: String,
+    layers: Vec<SceneryLayer>,
+}
+
+impl Level {
+    pub async fn load(path: &str) -> Result<Level, macroquad::Error> {
+        let file = LevelFile::deserialize_ron(&load_string(path).await?).map_err(|err| {
+            error!("{}: {}", path, err);
+            macroquad::Error::UnknownError("invalid level file")
+        })?;
+
+        let mut layers = vec![];
+        for layer in file.layers {
+            let texture = load_texture_from_file(&layer.sheet).await?;
+            layers.push(SceneryLayer {
+                source_size: texture.size() / vec2(layer.columns.max(1) as f32, 1.0),
+                texture,
+                tiles: layer
+                    .rows
+                    .iter()
+                    .map(|row| row.chars().map(|c| c.to_digit(10)).collect())
+                    .collect(),
+                tile_size: vec2(layer.tile_width, layer.tile_height),
+                scroll_speed: layer.scroll_speed,
+                depth: layer.depth,
+                solid: layer.solid,
+                repeat: layer.repeat,
+            });
+        }
+
+        Ok(Level {
+            name: file.name,
+            layers,
+        })
+    }
+}
+
+/// Which level's scenery is showing and how far it has scrolled.
+#[derive(Default)]
+pub struct Scenery {
+    level: usize,
+    time: f32,
+}
+
+impl Scenery {
+    /// Starts scrolling a level from the beginning. Indices past the last level wrap around.
+    pub fn start(&mut self, level: usize) {
+        self.level = level;
+        self.time = 0.0;
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+    }
+
+    fn level<'a>(&self, levels: &'a [Level]) -> Option<&'a Level> {
+        levels.get(self.level.checked_rem(levels.len())?)
+    }
+
+    pub fn draw(&self, levels: &[Level], parallax: &Parallax) {
+        let Some(level) = self.level(levels) else {
+            return;
+        };
+
+        let color = Color::new(1.0, 1.0, 1.0, (self.time / FADE_IN).min(1.0));
+        for layer in &level.layers {
+            layer.visible_tiles(self.time, parallax, |rect, tile| {
+                draw_texture_ex(
+                    &layer.texture,
+                    rect.x,
+                    rect.y,
+                    color,
+                    DrawTextureParams {
+                        dest_size: Some(rect.size()),
+                        source: Some(Rect::new(
+                            tile as f32 * layer.source_size.x,
+                            0.0,
+                            layer.source_size.x,
+                            layer.source_size.y,
+                        )),
+                        ..Default::default()
+                    },
+                );
+            });
+        }
+    }
+
+    /// Name of the level while it has just started.
+    pub fn banner<'a>(&self, levels: &'a [Level]) -> Option<&'a str> {
+        let level = self.level(levels)?;
+        (self.time < BANNER_TIME).then_some(level.name.as_str())
+    }
+
+    /// Collects the on-screen rectangles of solid tiles into `obstacles`.
+    pub fn obstacles(&self, levels: &[Level], parallax: &Parallax, obstacles: &mut Vec<Rect>) {
+        obstacles.clear();
+        let Some(level) = self.level(levels) else {
+            return;
+        };
+
+        for layer in level.layers.iter().filter(|layer| layer.solid) {
+            layer.visible_tiles(self.time, parallax, |rect, _| {
+                obstacles.push(Rect::new(
+                    rect.x + OBSTACLE_INSET,
+                    rect.y + OBSTACLE_INSET,
+                    rect.w - OBSTACLE_INSET * 2.0,
+                    rect.h - OBSTACLE_INSET * 2.0,
+                ));
+            });
+        }
+    }
+}
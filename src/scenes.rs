@@ -0,0 +1,680 @@
+use macroquad::prelude::*;
+use macroquad::ui::{hash, root_ui, widgets::Window, Id};
+use macroquad_particles::Emitter;
+use nanoserde::SerRon;
+
+use crate::{
+    achievements,
+    canvas::{self, Scaling},
+    difficulty::Difficulty,
+    draw_text_centered,
+    effects::Effects,
+    events::GameEvent,
+    game::Game,
+    particles::{ParticleEffect, ParticlePreset, ParticlePresets},
+    resources::Resources,
+    scoring,
+    stats::format_duration,
+    transition::{Effect, ScreenFader},
+};
+
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+}
+
+/// A screen of the game. Scenes further down the stack keep their state but only the top one
+/// is updated, so e.g. Paused sits on top of Playing and popping it resumes the run.
+pub trait Scene {
+    /// Called when the scene is pushed or replaces another one.
+    fn enter(&mut self, _game: &mut Game, _resources: &Resources) {}
+
+    /// Called when the scene is popped or replaced.
+    fn exit(&mut self, _game: &mut Game, _resources: &Resources) {}
+
+    /// Called before the world is drawn.
+    fn update(&mut self, _game: &mut Game, _resources: &Resources) -> Transition {
+        Transition::None
+    }
+
+    /// Called after the world is drawn, for menus and other overlays.
+    fn draw(&mut self, _game: &mut Game, _resources: &Resources) -> Transition {
+        Transition::None
+    }
+
+    /// Type name of the scene, for the debug overlay.
+    #[cfg(debug_assertions)]
+    fn name(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        path.rsplit("::").next().unwrap_or(path)
+    }
+
+    /// Whether the run is in progress while this scene is on top.
+    fn is_playing(&self) -> bool {
+        false
+    }
+
+    /// Screen effect played when another scene is replaced by this one. Pushes and pops are
+    /// always instant so pausing stays responsive.
+    fn entrance(&self) -> Option<Effect> {
+        None
+    }
+}
+
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    fader: ScreenFader,
+}
+
+impl SceneStack {
+    pub fn new(
+        mut initial: Box<dyn Scene>,
+        fader: ScreenFader,
+        game: &mut Game,
+        resources: &Resources,
+    ) -> Self {
+        initial.enter(game, resources);
+        game.events.emit(GameEvent::StateChanged {
+            playing: initial.is_playing(),
+        });
+        SceneStack {
+            scenes: vec![initial],
+            fader,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.scenes.last().is_some_and(|scene| scene.is_playing())
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn scene_name(&self) -> &'static str {
+        self.scenes.last().map_or("none", |scene| scene.name())
+    }
+
+    /// How much of the screen the current transition covers, from 0 to 1.
+    pub fn transition_coverage(&self) -> f32 {
+        self.fader.coverage()
+    }
+
+    pub fn update(&mut self, game: &mut Game, resources: &Resources) {
+        if let Some(transition) = self.fader.update(get_frame_time()) {
+            self.apply(transition, game, resources);
+        }
+
+        // Input is locked while a transition plays, so the scene underneath doesn't react
+        if self.fader.is_active() {
+            return;
+        }
+
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.update(game, resources);
+            self.request(transition, game, resources);
+        }
+    }
+
+    pub fn draw(&mut self, game: &mut Game, resources: &Resources) {
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.draw(game, resources);
+            if !self.fader.is_active() {
+                self.request(transition, game, resources);
+            }
+        }
+        self.fader.draw();
+    }
+
+    /// Starts the incoming scene's entrance effect if it has one, otherwise applies the
+    /// transition straight away.
+    fn request(&mut self, transition: Transition, game: &mut Game, resources: &Resources) {
+        let entrance = match &transition {
+            Transition::Replace(scene) => scene.entrance(),
+            _ => None,
+        };
+        match entrance {
+            Some(effect) => self.fader.start(effect, transition),
+            None => self.apply(transition, game, resources),
+        }
+    }
+
+    /// Applies a transition, letting the game know if that started or stopped the run.
+    fn apply(&mut self, transition: Transition, game: &mut Game, resources: &Resources) {
+        let was_playing = self.is_playing();
+        match transition {
+            Transition::None => {}
+            Transition::Push(mut scene) => {
+                scene.enter(game, resources);
+                self.scenes.push(scene);
+            }
+            Transition::Pop => {
+                // The bottom scene stays put so there's always something to show
+                if self.scenes.len() > 1 {
+                    let mut scene = self.scenes.pop().unwrap();
+                    scene.exit(game, resources);
+                }
+            }
+            Transition::Replace(mut scene) => {
+                if let Some(mut old) = self.scenes.pop() {
+                    old.exit(game, resources);
+                }
+                scene.enter(game, resources);
+                self.scenes.push(scene);
+            }
+        }
+        if self.is_playing() != was_playing {
+            game.events.emit(GameEvent::StateChanged {
+                playing: self.is_playing(),
+            });
+        }
+    }
+}
+
+/// Menus are drawn in window coordinates, so they're placed over the canvas viewport every
+/// frame and shrunk to fit it when the window is small, scrolling what doesn't fit.
+fn centered_window(id: Id, scaling: Scaling, size: Vec2) -> Window {
+    let viewport = canvas::viewport(scaling);
+    let size = size.min(viewport.size());
+    menu_window(id, viewport.center() - size / 2.0, size)
+}
+
+/// Like `centered_window`, but along the bottom of the canvas, below what the screen draws.
+fn bottom_window(id: Id, scaling: Scaling, size: Vec2) -> Window {
+    let viewport = canvas::viewport(scaling);
+    let size = size.min(viewport.size());
+    let position = vec2(
+        viewport.center().x - size.x / 2.0,
+        viewport.bottom() - size.y,
+    );
+    menu_window(id, position, size)
+}
+
+fn menu_window(id: Id, position: Vec2, size: Vec2) -> Window {
+    // Movable windows only take their position on the first frame
+    Window::new(id, position, size)
+        .titlebar(false)
+        .movable(false)
+}
+
+pub struct MainMenu;
+
+impl Scene for MainMenu {
+    fn draw(&mut self, game: &mut Game, resources: &Resources) -> Transition {
+        let window_size = vec2(370.0, 600.0);
+        let mut transition = Transition::None;
+        let window = centered_window(hash!(), game.settings.scaling, window_size);
+        window.ui(&mut root_ui(), |ui| {
+            ui.label(vec2(80.0, -34.0), "Main Menu");
+            if ui.button(vec2(65.0, 25.0), "Play") {
+                transition = Transition::Replace(Box::new(DifficultySelect));
+            }
+            if ui.button(vec2(20.0, 105.0), "Options") {
+                transition = Transition::Push(Box::new(Options));
+            }
+            if ui.button(vec2(50.0, 185.0), "Stats") {
+                transition = Transition::Push(Box::new(StatsScreen));
+            }
+            if ui.button(vec2(35.0, 265.0), "Awards") {
+                transition = Transition::Push(Box::new(AchievementsScreen));
+            }
+            if ui.button(vec2(35.0, 345.0), "FX Lab") {
+                transition = Transition::Push(Box::new(ParticlePreview::new(&resources.particles)));
+            }
+            if ui.button(vec2(65.0, 425.0), "Quit") {
+                std::process::exit(0);
+            }
+        });
+        transition
+    }
+
+    fn entrance(&self) -> Option<Effect> {
+        Some(Effect::Fade)
+    }
+}
+
+/// Picks the difficulty before a run. It takes the main menu's place rather than sitting on
+/// top of it, so starting the run leaves nothing behind on the stack.
+pub struct DifficultySelect;
+
+impl Scene for DifficultySelect {
+    fn update(&mut self, _game: &mut Game, _resources: &Resources) -> Transition {
+        if is_key_pressed(KeyCode::Escape) {
+            Transition::Replace(Box::new(MainMenu))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        let window_size = vec2(370.0, 580.0);
+        let mut transition = Transition::None;
+        let settings = &mut game.settings;
+        let window = centered_window(hash!(), settings.scaling, window_size);
+        window.ui(&mut root_ui(), |ui| {
+            ui.label(vec2(60.0, -34.0), "Difficulty");
+            ui.checkbox(hash!(), "Adaptive", &mut settings.adaptive_difficulty);
+            let buttons = [
+                (Difficulty::Easy, 75.0),
+                (Difficulty::Normal, 35.0),
+                (Difficulty::Hard, 75.0),
+                (Difficulty::Insane, 45.0),
+            ];
+            for (i, (difficulty, x)) in buttons.into_iter().enumerate() {
+                if ui.button(vec2(x, 45.0 + i as f32 * 95.0), difficulty.name()) {
+                    settings.difficulty = difficulty;
+                    transition = Transition::Replace(Box::new(Playing));
+                }
+            }
+            if ui.button(vec2(65.0, 425.0), "Back") {
+                transition = Transition::Replace(Box::new(MainMenu));
+            }
+        });
+        transition
+    }
+}
+
+pub struct Playing;
+
+impl Scene for Playing {
+    fn enter(&mut self, game: &mut Game, _resources: &Resources) {
+        game.new_run();
+    }
+
+    fn exit(&mut self, game: &mut Game, _resources: &Resources) {
+        game.end_run();
+    }
+
+    fn update(&mut self, game: &mut Game, resources: &Resources) -> Transition {
+        if game.update(resources, get_frame_time()) {
+            Transition::Replace(Box::new(GameOver))
+        } else if is_key_pressed(KeyCode::Escape) {
+            Transition::Push(Box::new(Paused))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        true
+    }
+
+    fn entrance(&self) -> Option<Effect> {
+        Some(Effect::Dissolve)
+    }
+}
+
+pub struct Paused;
+
+impl Scene for Paused {
+    fn draw(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        let window_size = vec2(370.0, 320.0);
+        let mut transition = Transition::None;
+        let window = centered_window(hash!(), game.settings.scaling, window_size);
+        window.ui(&mut root_ui(), |ui| {
+            ui.label(vec2(80.0, -34.0), "Paused");
+            if ui.button(vec2(40.0, 25.0), "Resume") {
+                transition = Transition::Pop;
+            }
+            if ui.button(vec2(20.0, 125.0), "Options") {
+                transition = Transition::Push(Box::new(Options));
+            }
+        });
+        transition
+    }
+}
+
+pub struct GameOver;
+
+impl Scene for GameOver {
+    fn update(&mut self, _game: &mut Game, _resources: &Resources) -> Transition {
+        if is_key_pressed(KeyCode::Enter) {
+            Transition::Replace(Box::new(MainMenu))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        draw_text_centered("GAME OVER!", -3.0);
+        if game.got_high_score {
+            draw_text_centered("NEW HIGH SCORE!", -2.0);
+        }
+
+        let run = &game.run;
+        let [small, medium, big] = run.kills;
+        let breakdown = [
+            format!("Score {}", game.score),
+            format!("Kills: {} small, {} medium, {} big", small, medium, big),
+            format!(
+                "Accuracy {:.0}% ({} of {} shots)",
+                run.accuracy() * 100.0,
+                run.hits,
+                run.shots_fired
+            ),
+            format!(
+                "Max combo {} kills (x{})",
+                run.max_combo,
+                scoring::multiplier(run.max_combo)
+            ),
+            "Press Enter".to_string(),
+        ];
+        for (i, line) in breakdown.iter().enumerate() {
+            let size = measure_text(line, None, 25, 1.0);
+            draw_text(
+                line,
+                canvas::WIDTH / 2.0 - size.width / 2.0,
+                canvas::HEIGHT / 2.0 + i as f32 * 35.0,
+                25.0,
+                WHITE,
+            );
+        }
+        Transition::None
+    }
+
+    fn entrance(&self) -> Option<Effect> {
+        Some(Effect::Wipe)
+    }
+}
+
+/// Lifetime totals and the last run, on top of the main menu.
+pub struct StatsScreen;
+
+impl Scene for StatsScreen {
+    fn update(&mut self, _game: &mut Game, _resources: &Resources) -> Transition {
+        if is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        let lifetime = &game.lifetime;
+        let totals = &lifetime.totals;
+        let kills = |kills: [u32; 3]| format!("{} / {} / {}", kills[0], kills[1], kills[2]);
+        let lines = [
+            "LIFETIME".to_string(),
+            format!("Runs {}", lifetime.runs),
+            format!("Play time {}", format_duration(totals.play_time)),
+            format!("Longest run {}", format_duration(lifetime.longest_run)),
+            format!(
+                "Shots {}  hits {}  accuracy {:.0}%",
+                totals.shots_fired,
+                totals.hits,
+                totals.accuracy() * 100.0
+            ),
+            format!("Kills S/M/L {}", kills(totals.kills)),
+            format!(
+                "Deaths S/M/L {}  crashes {}",
+                kills(totals.deaths),
+                totals.crashes
+            ),
+            format!("Best combo {}", totals.max_combo),
+            String::new(),
+            "LAST RUN".to_string(),
+            format!(
+                "Time {}  kills {}  accuracy {:.0}%",
+                format_duration(game.run.time_survived),
+                game.run.total_kills(),
+                game.run.accuracy() * 100.0
+            ),
+        ];
+        draw_rectangle(
+            0.0,
+            0.0,
+            canvas::WIDTH,
+            canvas::HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 30.0, 60.0 + i as f32 * 30.0, 25.0, WHITE);
+        }
+
+        let window_size = vec2(370.0, 150.0);
+        let window = bottom_window(hash!(), game.settings.scaling, window_size);
+        let mut transition = Transition::None;
+        window.ui(&mut root_ui(), |ui| {
+            if ui.button(vec2(65.0, 0.0), "Back") {
+                transition = Transition::Pop;
+            }
+        });
+        transition
+    }
+}
+
+/// Every achievement with when it was unlocked, on top of the main menu.
+pub struct AchievementsScreen;
+
+impl Scene for AchievementsScreen {
+    fn update(&mut self, _game: &mut Game, _resources: &Resources) -> Transition {
+        if is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&mut self, game: &mut Game, resources: &Resources) -> Transition {
+        draw_rectangle(
+            0.0,
+            0.0,
+            canvas::WIDTH,
+            canvas::HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        let unlocked = game.achievements.unlocked_count();
+        let total = resources.achievements.len();
+        draw_text(
+            format!("ACHIEVEMENTS  {} of {}", unlocked.min(total), total).as_str(),
+            30.0,
+            60.0,
+            25.0,
+            WHITE,
+        );
+        for (i, achievement) in resources.achievements.iter().enumerate() {
+            let y = 105.0 + i as f32 * 50.0;
+            let (status, color) = match game.achievements.unlocked_at(&achievement.id) {
+                Some(timestamp) => (achievements::format_date(timestamp), GOLD),
+                None => ("Locked".to_string(), GRAY),
+            };
+            draw_text(&achievement.name, 30.0, y, 25.0, color);
+            let size = measure_text(&status, None, 20, 1.0);
+            draw_text(&status, canvas::WIDTH - size.width - 30.0, y, 20.0, color);
+            draw_text(&achievement.description, 30.0, y + 20.0, 20.0, LIGHTGRAY);
+        }
+
+        let window_size = vec2(370.0, 150.0);
+        let window = bottom_window(hash!(), game.settings.scaling, window_size);
+        let mut transition = Transition::None;
+        window.ui(&mut root_ui(), |ui| {
+            if ui.button(vec2(65.0, 0.0), "Back") {
+                transition = Transition::Pop;
+            }
+        });
+        transition
+    }
+}
+
+/// Overlays whichever menu opened it; Back returns there.
+pub struct Options;
+
+impl Scene for Options {
+    fn draw(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        let window_size = vec2(370.0, 480.0);
+        let mut transition = Transition::None;
+        let settings = &mut game.settings;
+        let window = centered_window(hash!(), settings.scaling, window_size);
+        window.ui(&mut root_ui(), |ui| {
+            ui.label(vec2(80.0, -34.0), "Options");
+            ui.slider(hash!(), "Music", 0.0..1.0, &mut settings.music_volume);
+            ui.slider(hash!(), "Effects", 0.0..1.0, &mut settings.sfx_volume);
+            let mut pixel_perfect = settings.scaling == Scaling::Integer;
+            ui.checkbox(hash!(), "Pixel perfect", &mut pixel_perfect);
+            settings.scaling = if pixel_perfect {
+                Scaling::Integer
+            } else {
+                Scaling::Aspect
+            };
+            ui.checkbox(hash!(), "Low resolution", &mut settings.low_res);
+            ui.checkbox(hash!(), "Bloom", &mut settings.bloom);
+            ui.checkbox(
+                hash!(),
+                "Hit aberration",
+                &mut settings.chromatic_aberration,
+            );
+            ui.checkbox(hash!(), "Scanlines", &mut settings.scanlines);
+            ui.checkbox(hash!(), "CRT", &mut settings.crt);
+            ui.slider(hash!(), "Shake", 0.0..1.0, &mut settings.screen_shake);
+            ui.checkbox(hash!(), "Follow ship", &mut settings.camera_follow);
+            if ui.button(vec2(65.0, 335.0), "Back") {
+                transition = Transition::Pop;
+            }
+        });
+        transition
+    }
+}
+
+/// Seconds between automatic replays of one-shot presets in the preview.
+const PREVIEW_REPLAY_INTERVAL: f32 = 1.5;
+
+/// Plays one particle preset at a time for tuning. The sliders edit a copy of the presets,
+/// which can be printed to the log and pasted back into the file.
+pub struct ParticlePreview {
+    presets: ParticlePresets,
+    selected: usize,
+    /// Borrowed from the effects pool while the screen is open.
+    emitter: Option<Emitter>,
+    /// The preset `emitter` was last set up from, to notice slider changes.
+    shown: ParticlePreset,
+    since_fired: f32,
+    status: String,
+}
+
+impl ParticlePreview {
+    pub fn new(presets: &ParticlePresets) -> Self {
+        ParticlePreview {
+            presets: presets.clone(),
+            selected: 0,
+            emitter: None,
+            shown: presets.get(ParticleEffect::ALL[0]).clone(),
+            since_fired: 0.0,
+            status: String::new(),
+        }
+    }
+
+    fn effect(&self) -> ParticleEffect {
+        ParticleEffect::ALL[self.selected]
+    }
+
+    /// Swaps the emitter for one of preset `selected` and fires it.
+    fn select(&mut self, effects: &mut Effects, selected: usize) {
+        if let Some(emitter) = self.emitter.take() {
+            effects.put_back(self.effect(), emitter);
+        }
+        self.selected = selected;
+        let effect = self.effect();
+        self.emitter = Some(effects.take(effect, self.presets.config(effect)));
+        self.fire();
+    }
+
+    /// Sets the emitter up from the current preset and starts it again.
+    fn fire(&mut self) {
+        let effect = self.effect();
+        if let Some(emitter) = &mut self.emitter {
+            emitter.config = self.presets.config(effect);
+        }
+        self.shown = self.presets.get(effect).clone();
+        self.since_fired = 0.0;
+    }
+}
+
+impl Scene for ParticlePreview {
+    fn enter(&mut self, game: &mut Game, _resources: &Resources) {
+        self.select(&mut game.world.effects, self.selected);
+    }
+
+    fn exit(&mut self, game: &mut Game, _resources: &Resources) {
+        if let Some(emitter) = self.emitter.take() {
+            game.world.effects.put_back(self.effect(), emitter);
+        }
+    }
+
+    fn update(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        let count = ParticleEffect::ALL.len();
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        if is_key_pressed(KeyCode::Right) {
+            let selected = (self.selected + 1) % count;
+            self.select(&mut game.world.effects, selected);
+        }
+        if is_key_pressed(KeyCode::Left) {
+            let selected = (self.selected + count - 1) % count;
+            self.select(&mut game.world.effects, selected);
+        }
+        if is_key_pressed(KeyCode::Space) {
+            self.fire();
+        }
+        if is_key_pressed(KeyCode::R) {
+            self.status = match self.presets.reload() {
+                Ok(()) => "Reloaded".to_string(),
+                Err(err) => err,
+            };
+            self.fire();
+        }
+        if is_key_pressed(KeyCode::P) {
+            let effect = self.effect();
+            info!(
+                "{}: {}",
+                effect.name(),
+                self.presets.get(effect).serialize_ron()
+            );
+            self.status = "Printed to the log".to_string();
+        }
+
+        self.since_fired += get_frame_time();
+        if self.shown.one_shot && self.since_fired > PREVIEW_REPLAY_INTERVAL {
+            self.fire();
+        }
+        Transition::None
+    }
+
+    fn draw(&mut self, game: &mut Game, _resources: &Resources) -> Transition {
+        if *self.presets.get(self.effect()) != self.shown {
+            self.fire();
+        }
+        if let Some(emitter) = &mut self.emitter {
+            emitter.draw(vec2(canvas::WIDTH / 2.0, canvas::HEIGHT / 3.0));
+        }
+
+        let help = [
+            "Left/Right: preset   Space: fire",
+            "R: reload file   P: print   Esc: back",
+        ];
+        for (line, text) in help.iter().enumerate() {
+            draw_text(text, 10.0, 25.0 + line as f32 * 20.0, 20.0, WHITE);
+        }
+        draw_text(&self.status, 10.0, 70.0, 20.0, YELLOW);
+
+        let window_size = vec2(370.0, 330.0);
+        let window = bottom_window(hash!(), game.settings.scaling, window_size);
+        let mut transition = Transition::None;
+        let effect = self.effect();
+        let preset = self.presets.get_mut(effect);
+        window.ui(&mut root_ui(), |ui| {
+            ui.label(vec2(20.0, -34.0), effect.name());
+            let mut amount = preset.amount as f32;
+            ui.slider(hash!(), "Amount", 1.0..400.0, &mut amount);
+            preset.amount = amount.round() as u32;
+            ui.slider(hash!(), "Lifetime", 0.05..3.0, &mut preset.lifetime);
+            ui.slider(hash!(), "Explosive", 0.0..1.0, &mut preset.explosiveness);
+            ui.slider(hash!(), "Velocity", 0.0..800.0, &mut preset.velocity);
+            ui.slider(hash!(), "Spread", 0.0..360.0, &mut preset.spread);
+            ui.slider(hash!(), "Size", 1.0..40.0, &mut preset.size);
+            if ui.button(vec2(65.0, 185.0), "Back") {
+                transition = Transition::Pop;
+            }
+        });
+        transition
+    }
+}
@@ -0,0 +1,127 @@
+use macroquad::prelude::*;
+
+use crate::events::{EventBus, GameEvent};
+
+/// Seconds after a kill for the next one to continue the combo.
+const COMBO_WINDOW: f32 = 2.0;
+/// Once the window has passed, the combo drops a step this often until it's gone.
+const COMBO_DECAY_INTERVAL: f32 = 0.75;
+/// Kills per step of the multiplier.
+const COMBO_STEP: u32 = 3;
+const MAX_MULTIPLIER: u32 = 8;
+/// Seconds a score popup stays up.
+const POPUP_LIFETIME: f32 = 1.0;
+/// Pixels per second popups float up.
+const POPUP_RISE: f32 = 40.0;
+const POPUP_TEXT_SIZE: f32 = 20.0;
+
+/// Chain of quick kills. The multiplier goes up a step every few kills in a row, and steps
+/// back down when the player stops shooting things.
+#[derive(Default)]
+pub struct Combo {
+    chain: u32,
+    /// Seconds until the chain drops a step.
+    timer: f32,
+}
+
+impl Combo {
+    pub fn chain(&self) -> u32 {
+        self.chain
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        multiplier(self.chain)
+    }
+
+    /// Adds a kill to the chain. Returns the multiplier for it.
+    pub fn kill(&mut self) -> u32 {
+        self.chain += 1;
+        self.timer = COMBO_WINDOW;
+        self.multiplier()
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.chain == 0 {
+            return;
+        }
+        self.timer -= delta_time;
+        if self.timer <= 0.0 {
+            self.chain = self.chain.saturating_sub(COMBO_STEP);
+            self.timer = COMBO_DECAY_INTERVAL;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Combo::default();
+    }
+}
+
+/// Points for kills, with the combo multiplier.
+pub fn register_subscribers(events: &mut EventBus) {
+    events.subscribe(|game, _, event| {
+        if let GameEvent::EnemyKilled { pos, points, .. } = *event {
+            let multiplier = game.combo.kill();
+            game.add_score(points * multiplier);
+            game.popups.spawn(pos, points, multiplier);
+            game.run.max_combo = game.run.max_combo.max(game.combo.chain());
+        }
+    });
+}
+
+/// Score multiplier for a chain of `chain` kills.
+pub fn multiplier(chain: u32) -> u32 {
+    (1 + chain.saturating_sub(1) / COMBO_STEP).min(MAX_MULTIPLIER)
+}
+
+struct Popup {
+    pos: Vec2,
+    text: String,
+    age: f32,
+}
+
+/// Floating "+64 x3" texts where points were scored.
+#[derive(Default)]
+pub struct ScorePopups {
+    popups: Vec<Popup>,
+}
+
+impl ScorePopups {
+    /// Shows `points` before the multiplier, e.g. "+64 x3".
+    pub fn spawn(&mut self, pos: Vec2, points: u32, multiplier: u32) {
+        let text = if multiplier > 1 {
+            format!("+{} x{}", points, multiplier)
+        } else {
+            format!("+{}", points)
+        };
+        self.popups.push(Popup {
+            pos,
+            text,
+            age: 0.0,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.popups.clear();
+    }
+
+    /// Ages and draws the popups, fading them out as they rise.
+    pub fn draw(&mut self, delta_time: f32) {
+        for popup in &mut self.popups {
+            popup.age += delta_time;
+            popup.pos.y -= POPUP_RISE * delta_time;
+        }
+        self.popups.retain(|popup| popup.age < POPUP_LIFETIME);
+
+        for popup in &self.popups {
+            let size = measure_text(&popup.text, None, POPUP_TEXT_SIZE as u16, 1.0);
+            let alpha = 1.0 - popup.age / POPUP_LIFETIME;
+            draw_text(
+                &popup.text,
+                popup.pos.x - size.width / 2.0,
+                popup.pos.y,
+                POPUP_TEXT_SIZE,
+                Color::new(1.0, 0.9, 0.3, alpha),
+            );
+        }
+    }
+}
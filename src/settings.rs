@@ -0,0 +1,44 @@
+use crate::{canvas::Scaling, difficulty::Difficulty};
+
+/// Player preferences, edited from the Options screen.
+pub struct Settings {
+    /// Multiplier applied to the music volume of every scene.
+    pub music_volume: f32,
+    /// Multiplier applied to sound effects.
+    pub sfx_volume: f32,
+    pub scaling: Scaling,
+    /// Render at a fraction of the canvas size for a retro look and cheaper shading.
+    pub low_res: bool,
+    pub bloom: bool,
+    /// Colour fringing when something is hit.
+    pub chromatic_aberration: bool,
+    pub scanlines: bool,
+    /// Screen curvature and vignette of an old tube.
+    pub crt: bool,
+    /// Multiplier for screen shake, 0 for none.
+    pub screen_shake: f32,
+    /// Lean the view slightly towards the ship.
+    pub camera_follow: bool,
+    pub difficulty: Difficulty,
+    /// Adjust the difficulty to how well the player is doing.
+    pub adaptive_difficulty: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            scaling: Scaling::Aspect,
+            low_res: false,
+            bloom: true,
+            chromatic_aberration: true,
+            scanlines: false,
+            crt: false,
+            screen_shake: 1.0,
+            camera_follow: false,
+            difficulty: Difficulty::Normal,
+            adaptive_difficulty: false,
+        }
+    }
+}
@@ -1,4 +1,9 @@
 pub const FRAGMENT_SHADER: &str = include_str!("starfield-shader.glsl");
+pub const DISSOLVE_FRAGMENT_SHADER: &str = include_str!("dissolve-shader.glsl");
+pub const BLOOM_FRAGMENT_SHADER: &str = include_str!("bloom-shader.glsl");
+pub const ABERRATION_FRAGMENT_SHADER: &str = include_str!("aberration-shader.glsl");
+pub const SCANLINES_FRAGMENT_SHADER: &str = include_str!("scanlines-shader.glsl");
+pub const CRT_FRAGMENT_SHADER: &str = include_str!("crt-shader.glsl");
 
 pub const VERTEX_SHADER: &str = "#version 100
 attribute vec3 position;
@@ -15,3 +20,19 @@ void main() {
     iTime = _Time.x;
 }
 ";
+
+/// Vertex shader for full-screen passes that sample the previous pass through `uv`.
+pub const POST_VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+varying vec2 uv;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}
+";
@@ -0,0 +1,150 @@
+use macroquad::{
+    experimental::animation::{AnimatedSprite, Animation, AnimationFrame},
+    texture::Texture2D,
+};
+
+use crate::{collision::FrameHitboxes, components::EnemyKind, resources::Resources};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SpriteSheet {
+    Ship,
+    Bullet,
+    Enemy(EnemyKind),
+}
+
+impl SpriteSheet {
+    pub fn texture(self, resources: &Resources) -> &Texture2D {
+        match self {
+            SpriteSheet::Ship => &resources.ship_texture,
+            SpriteSheet::Bullet => &resources.bullet_texture,
+            SpriteSheet::Enemy(EnemyKind::Small) => &resources.enemy_small_texture,
+            SpriteSheet::Enemy(EnemyKind::Medium) => &resources.enemy_medium_texture,
+            SpriteSheet::Enemy(EnemyKind::Big) => &resources.enemy_big_texture,
+        }
+    }
+
+    pub fn hitboxes(self, resources: &Resources) -> &FrameHitboxes {
+        match self {
+            SpriteSheet::Ship => &resources.ship_hitboxes,
+            SpriteSheet::Bullet => &resources.bullet_hitboxes,
+            SpriteSheet::Enemy(kind) => &resources.enemy_hitboxes[kind.index()],
+        }
+    }
+}
+
+/// Animation state for every sprite sheet. All sprites drawn from the same sheet share it,
+/// so e.g. every small enemy flaps in sync.
+pub struct SpriteSheets {
+    pub ship: AnimatedSprite,
+    pub bullet: AnimatedSprite,
+    pub enemies: [AnimatedSprite; 3],
+}
+
+impl SpriteSheets {
+    pub fn new() -> Self {
+        let mut bullet = AnimatedSprite::new(
+            16,
+            16,
+            &[
+                Animation {
+                    name: "bullet".into(),
+                    row: 0,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "bolt".into(),
+                    row: 1,
+                    frames: 2,
+                    fps: 12,
+                },
+            ],
+            true,
+        );
+        bullet.set_animation(1);
+
+        let ship = AnimatedSprite::new(
+            16,
+            24,
+            &[
+                Animation {
+                    name: "idle".into(),
+                    row: 0,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "left".into(),
+                    row: 2,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "right".into(),
+                    row: 4,
+                    frames: 2,
+                    fps: 12,
+                },
+            ],
+            true,
+        );
+
+        let enemy_small = AnimatedSprite::new(
+            17,
+            16,
+            &[Animation {
+                name: "enemy_small".into(),
+                row: 0,
+                frames: 2,
+                fps: 12,
+            }],
+            true,
+        );
+
+        let enemy_medium = AnimatedSprite::new(
+            32,
+            16,
+            &[Animation {
+                name: "enemy_medium".into(),
+                row: 0,
+                frames: 2,
+                fps: 12,
+            }],
+            true,
+        );
+
+        let enemy_big = AnimatedSprite::new(
+            32,
+            32,
+            &[Animation {
+                name: "enemy_big".into(),
+                row: 0,
+                frames: 2,
+                fps: 12,
+            }],
+            true,
+        );
+
+        SpriteSheets {
+            ship,
+            bullet,
+            enemies: [enemy_small, enemy_medium, enemy_big],
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.ship.update();
+        self.bullet.update();
+        for enemy in &mut self.enemies {
+            enemy.update();
+        }
+    }
+
+    pub fn frame(&self, sheet: SpriteSheet) -> AnimationFrame {
+        match sheet {
+            SpriteSheet::Ship => self.ship.frame(),
+            SpriteSheet::Bullet => self.bullet.frame(),
+            SpriteSheet::Enemy(kind) => self.enemies[kind.index()].frame(),
+        }
+    }
+}
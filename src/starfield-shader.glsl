@@ -12,9 +12,16 @@ varying vec2 uv;
 varying float iTime;
 
 uniform vec2 iResolution;
-uniform float direction_modifier;
+// Sway of the view in screen heights, driven by the ship's movement
+uniform vec2 parallax;
+// Distance flown, integrated from the scroll speed on the CPU so speed changes don't jump
+uniform float travel;
+uniform float layers;
+uniform vec3 tint;
+// 0 for round stars, 1 for hyperspace streaks
+uniform float warp;
 
-#define NUM_LAYERS 4.
+#define MAX_LAYERS 8
 
 mat2 Rot(float a) {
     float s = sin(a), c = cos(a);
@@ -22,6 +29,7 @@ mat2 Rot(float a) {
 }
 
 float Star(vec2 uv, float flare) {
+    uv.y *= mix(1., .08, warp);
     float d = length(uv);
     float m = .05 / d;
 
@@ -57,7 +65,7 @@ vec3 StarLayer(vec2 uv) {
             float size = fract(n * 345.32);
             float star = Star(gv - offs - vec2(n, fract(n * 42.)) + .5, smoothstep(.9, 1., size) * .6);
             vec3 color = sin(vec3(.8, .8, .8) * fract(n * 2345.2) * 123.2) * .5 + .5;
-            color = color * vec3(0.25, 0.25, 0.20);
+            color = color * tint;
             star *= sin(iTime * 3. + n * 6.2831) * .5 + 1.;
             col += star * size * color;
         }
@@ -68,15 +76,18 @@ vec3 StarLayer(vec2 uv) {
 void main()
 {
     vec2 uv = (gl_FragCoord.xy - .5 * iResolution.xy) / iResolution.y;
-    float t = iTime * .02;
+    float t = travel * .02;
 
-    float speed = 3.0;
-    vec2 direction = vec2(-0.25 + direction_modifier, -1.0) * speed;
+    vec2 direction = vec2(-0.75, -3.0) + parallax;
 
     uv += direction;
     vec3 col = vec3(0);
 
-    for (float i = 0.; i < 1.; i += 1. / NUM_LAYERS) {
+    for (int layer = 0; layer < MAX_LAYERS; layer++) {
+        if (float(layer) >= layers) {
+            break;
+        }
+        float i = float(layer) / layers;
         float depth = fract(i+t);
         float scale = mix(20., .5, depth);
         float fade = depth * smoothstep(1., .9, depth);
@@ -0,0 +1,92 @@
+use macroquad::prelude::*;
+
+use crate::shader;
+
+/// How quickly the starfield eases towards new parameters, per second.
+const EASING_RATE: f32 = 2.0;
+
+/// Look of the background. Gameplay sets a target and `Starfield` eases towards it.
+#[derive(Clone, Copy)]
+pub struct StarfieldParams {
+    /// Scroll speed multiplier.
+    pub speed: f32,
+    /// Number of star layers, up to 8. Fractional values are rounded up.
+    pub layers: f32,
+    pub tint: Vec3,
+    /// 0 for round stars, 1 for hyperspace streaks.
+    pub warp: f32,
+}
+
+impl StarfieldParams {
+    pub const CRUISE: StarfieldParams = StarfieldParams {
+        speed: 1.0,
+        layers: 4.0,
+        tint: vec3(0.25, 0.25, 0.2),
+        warp: 0.0,
+    };
+
+    fn lerp(self, other: StarfieldParams, t: f32) -> StarfieldParams {
+        StarfieldParams {
+            speed: self.speed + (other.speed - self.speed) * t,
+            layers: self.layers + (other.layers - self.layers) * t,
+            tint: self.tint.lerp(other.tint, t),
+            warp: self.warp + (other.warp - self.warp) * t,
+        }
+    }
+}
+
+pub struct Starfield {
+    material: Material,
+    params: StarfieldParams,
+    travel: f32,
+}
+
+impl Starfield {
+    pub fn new() -> Result<Self, macroquad::Error> {
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: shader::VERTEX_SHADER,
+                fragment: shader::FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![
+                    UniformDesc::new("iResolution", UniformType::Float2),
+                    UniformDesc::new("parallax", UniformType::Float2),
+                    UniformDesc::new("travel", UniformType::Float1),
+                    UniformDesc::new("layers", UniformType::Float1),
+                    UniformDesc::new("tint", UniformType::Float3),
+                    UniformDesc::new("warp", UniformType::Float1),
+                ],
+                ..Default::default()
+            },
+        )?;
+
+        Ok(Starfield {
+            material,
+            params: StarfieldParams::CRUISE,
+            travel: 0.0,
+        })
+    }
+
+    pub fn update(&mut self, target: StarfieldParams, delta_time: f32) {
+        let t = 1.0 - (-EASING_RATE * delta_time).exp();
+        self.params = self.params.lerp(target, t);
+        self.travel += self.params.speed * delta_time;
+    }
+
+    /// Fills a `size` rectangle at the origin, shifted by `parallax` pixels. `resolution` is
+    /// the size of the render target in pixels, as the shader works in `gl_FragCoord` space.
+    pub fn draw(&self, size: Vec2, resolution: Vec2, parallax: Vec2) {
+        self.material.set_uniform("iResolution", resolution);
+        // The shader's coordinates span one unit per screen height
+        self.material.set_uniform("parallax", -parallax / size.y);
+        self.material.set_uniform("travel", self.travel);
+        self.material
+            .set_uniform("layers", self.params.layers.ceil());
+        self.material.set_uniform("tint", self.params.tint);
+        self.material.set_uniform("warp", self.params.warp);
+        gl_use_material(&self.material);
+        draw_rectangle(0.0, 0.0, size.x, size.y, WHITE);
+        gl_use_default_material();
+    }
+}
@@ -0,0 +1,122 @@
+use nanoserde::{DeRon, SerRon};
+
+use crate::{
+    components::EnemyKind,
+    events::{EventBus, GameEvent},
+    persist,
+    systems::DeathCause,
+};
+
+const STATS_FILE: &str = "stats.ron";
+
+/// How a run went. Lifetime totals are kept in the same shape.
+#[derive(Clone, Default, DeRon, SerRon)]
+#[nserde(default)]
+pub struct RunStats {
+    pub shots_fired: u32,
+    /// Enemies hit by shots, counting each one a shot goes through.
+    pub hits: u32,
+    /// Enemies shot down, by `EnemyKind::index`.
+    pub kills: [u32; 3],
+    /// Longest chain of kills.
+    pub max_combo: u32,
+    /// Seconds the ship lasted.
+    pub time_survived: f32,
+    /// Real seconds from the start of the run to its end, including pauses and slowdowns.
+    pub play_time: f32,
+    /// Deaths to each kind of enemy, by `EnemyKind::index`.
+    pub deaths: [u32; 3],
+    /// Deaths from flying into scenery.
+    pub crashes: u32,
+    /// Waves got through without firing a shot.
+    pub quiet_waves: u32,
+}
+
+impl RunStats {
+    /// Share of shots that hit something, from 0 to 1.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            (self.hits as f32 / self.shots_fired as f32).min(1.0)
+        }
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.iter().sum()
+    }
+
+    pub fn record_death(&mut self, cause: DeathCause) {
+        match cause {
+            DeathCause::Enemy(kind) => self.deaths[kind.index()] += 1,
+            DeathCause::Scenery => self.crashes += 1,
+        }
+    }
+
+    pub fn record_kill(&mut self, kind: EnemyKind) {
+        self.kills[kind.index()] += 1;
+    }
+}
+
+/// Totals over every run played, saved between sessions.
+#[derive(Default, DeRon, SerRon)]
+#[nserde(default)]
+pub struct LifetimeStats {
+    pub runs: u32,
+    /// Sums of every run, except `max_combo` which is the best one.
+    pub totals: RunStats,
+    /// Seconds of the longest run.
+    pub longest_run: f32,
+}
+
+impl LifetimeStats {
+    pub fn load() -> Self {
+        persist::load(STATS_FILE).unwrap_or_default()
+    }
+
+    /// Adds a finished run to the totals and saves them.
+    pub fn record(&mut self, run: &RunStats) {
+        let totals = &mut self.totals;
+        self.runs += 1;
+        totals.shots_fired += run.shots_fired;
+        totals.hits += run.hits;
+        for (total, kills) in totals.kills.iter_mut().zip(run.kills) {
+            *total += kills;
+        }
+        totals.max_combo = totals.max_combo.max(run.max_combo);
+        totals.time_survived += run.time_survived;
+        for (total, deaths) in totals.deaths.iter_mut().zip(run.deaths) {
+            *total += deaths;
+        }
+        totals.crashes += run.crashes;
+        totals.play_time += run.play_time;
+        totals.quiet_waves += run.quiet_waves;
+        self.longest_run = self.longest_run.max(run.time_survived);
+        persist::save(STATS_FILE, self);
+    }
+}
+
+/// Counts what happens during a run into its stats.
+pub fn register_subscribers(events: &mut EventBus) {
+    events.subscribe(|game, _, event| match *event {
+        GameEvent::ShotFired => game.run.shots_fired += 1,
+        GameEvent::EnemyHit { .. } => game.run.hits += 1,
+        GameEvent::EnemyKilled { kind, .. } => game.run.record_kill(kind),
+        GameEvent::PlayerHit {
+            cause,
+            destroyed: true,
+        } => game.run.record_death(cause),
+        _ => {}
+    });
+}
+
+/// Formats seconds as h:mm:ss, or m:ss under an hour.
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
@@ -0,0 +1,380 @@
+use macroquad::{
+    color::WHITE,
+    math::{Rect, Vec2},
+    shapes::{draw_circle, draw_circle_lines},
+    texture::{draw_texture_ex, DrawTextureParams},
+    time::get_frame_time,
+};
+
+use crate::{
+    collision::{Hitbox, SpatialGrid},
+    components::{Collider, ColliderShape, EnemyKind, Grazed, Layer, SpriteKind, Transform},
+    ecs::{Entity, World},
+    events::{EventBus, GameEvent},
+    resources::Resources,
+    sprites::{SpriteSheet, SpriteSheets},
+};
+
+/// Integrates velocities. Every transform's `prev` is updated, moving or not.
+pub fn movement(world: &mut World, delta_time: f32) {
+    for (entity, transform) in world.transforms.iter_mut() {
+        transform.prev = transform.pos;
+        if let Some(velocity) = world.velocities.get(entity) {
+            transform.pos += velocity.0 * delta_time;
+        }
+    }
+}
+
+/// Moves anchored entities to their targets. Anchored emitters whose target is gone finish
+/// their current cycle and are then cleaned up by `render`.
+pub fn follow_anchors(world: &mut World) {
+    let mut orphans = vec![];
+    for (entity, anchor) in world.anchors.iter() {
+        let Some(target) = world.transforms.get(anchor.target).map(|t| t.pos) else {
+            orphans.push(entity);
+            continue;
+        };
+        if let Some(transform) = world.transforms.get_mut(entity) {
+            transform.pos = target + anchor.offset;
+        }
+    }
+
+    for entity in orphans {
+        world.anchors.remove(entity);
+        if let Some(emitter) = world.emitters.get_mut(entity) {
+            emitter.emitter.config.one_shot = true;
+        }
+    }
+}
+
+/// Despawns entities that have left the screen in the direction they're travelling.
+pub fn cull(world: &mut World, screen_height: f32) {
+    let culled = world
+        .culls
+        .iter()
+        .filter_map(|(entity, cull)| {
+            let y = world.transforms.get(entity)?.pos.y;
+            let dy = world.velocities.get(entity)?.0.y;
+            let gone =
+                (dy > 0.0 && y >= screen_height + cull.margin) || (dy < 0.0 && y <= -cull.margin);
+            gone.then_some(entity)
+        })
+        .collect::<Vec<_>>();
+    for entity in culled {
+        world.despawn(entity);
+    }
+}
+
+pub enum Contact {
+    Hit {
+        projectile: Entity,
+        target: Entity,
+    },
+    ShipHit(Entity),
+    Graze(Entity),
+    Collect(Entity),
+    /// The ship flew into solid scenery.
+    Crash,
+}
+
+/// World space hitbox of a collider.
+pub fn hitbox(
+    collider: &Collider,
+    transform: &Transform,
+    resources: &Resources,
+    sheets: &SpriteSheets,
+) -> Hitbox {
+    match &collider.shape {
+        ColliderShape::Frames(sheet) => sheet
+            .hitboxes(resources)
+            .get(&sheets.frame(*sheet))
+            .placed(transform.pos, transform.size),
+        ColliderShape::Fixed(hitbox) => hitbox.placed(transform.pos, transform.size),
+    }
+}
+
+/// Something shots or the ship can run into.
+#[derive(Clone, Copy)]
+enum Target {
+    Enemy(Entity),
+    Pickup(Entity),
+    /// Solid scenery.
+    Obstacle,
+}
+
+/// Finds every contact between colliders, using a grid broadphase over everything that can be
+/// run into: enemies, pickups and scenery.
+pub struct CollisionSystem {
+    grid: SpatialGrid,
+    candidates: Vec<usize>,
+    targets: Vec<(Target, Hitbox, Vec2)>,
+}
+
+impl CollisionSystem {
+    pub fn new(cell_size: f32) -> Self {
+        CollisionSystem {
+            grid: SpatialGrid::new(cell_size),
+            candidates: vec![],
+            targets: vec![],
+        }
+    }
+
+    /// `obstacles` are screen rectangles of solid scenery.
+    pub fn run(
+        &mut self,
+        world: &World,
+        resources: &Resources,
+        sheets: &SpriteSheets,
+        obstacles: &[Rect],
+    ) -> Vec<Contact> {
+        let colliders = |layer: Layer| {
+            world
+                .colliders
+                .iter()
+                .filter_map(move |(entity, collider)| {
+                    let transform = world.transforms.get(entity)?;
+                    (collider.layer == layer).then_some((entity, collider, transform))
+                })
+        };
+
+        self.grid.clear();
+        self.targets.clear();
+        let movers = colliders(Layer::Enemy)
+            .map(|(entity, collider, transform)| (Target::Enemy(entity), collider, transform))
+            .chain(
+                colliders(Layer::Pickup).map(|(entity, collider, transform)| {
+                    (Target::Pickup(entity), collider, transform)
+                }),
+            );
+        for (target, collider, transform) in movers {
+            let hitbox = hitbox(collider, transform, resources, sheets);
+            let offset = transform.prev - transform.pos;
+            self.grid
+                .insert(self.targets.len(), hitbox.swept(offset).bounds());
+            self.targets.push((target, hitbox, offset));
+        }
+        for obstacle in obstacles {
+            self.grid.insert(self.targets.len(), *obstacle);
+            self.targets
+                .push((Target::Obstacle, Hitbox::Rect(*obstacle), Vec2::ZERO));
+        }
+
+        // Hits come first so that enemies shot down this tick can't also hit the ship
+        let mut contacts = vec![];
+        for (projectile, collider, transform) in colliders(Layer::PlayerShot) {
+            let hitbox = hitbox(collider, transform, resources, sheets);
+            let offset = transform.prev - transform.pos;
+            self.grid
+                .query(hitbox.swept(offset).bounds(), &mut self.candidates);
+            for &i in &self.candidates {
+                let (Target::Enemy(target), target_hitbox, target_offset) = &self.targets[i] else {
+                    continue;
+                };
+                // Sweep the bullet relative to the enemy so that hits in between ticks count
+                if hitbox
+                    .swept(offset - *target_offset)
+                    .collides_with(target_hitbox)
+                {
+                    contacts.push(Contact::Hit {
+                        projectile,
+                        target: *target,
+                    });
+                }
+            }
+        }
+
+        for (_, collider, transform) in colliders(Layer::Player) {
+            let frame = sheets.frame(SpriteSheet::Ship);
+            let core = hitbox(collider, transform, resources, sheets);
+            let graze = resources
+                .ship_graze_hitboxes
+                .get(&frame)
+                .placed(transform.pos, transform.size);
+            self.grid.query(
+                graze.bounds().combine_with(core.bounds()),
+                &mut self.candidates,
+            );
+            let mut crashed = false;
+            for &i in &self.candidates {
+                let (target, target_hitbox, _) = &self.targets[i];
+                match *target {
+                    Target::Enemy(enemy) => {
+                        if core.collides_with(target_hitbox) {
+                            contacts.push(Contact::ShipHit(enemy));
+                        } else if graze.collides_with(target_hitbox) {
+                            contacts.push(Contact::Graze(enemy));
+                        }
+                    }
+                    Target::Pickup(pickup) => {
+                        if graze.collides_with(target_hitbox) {
+                            contacts.push(Contact::Collect(pickup));
+                        }
+                    }
+                    Target::Obstacle => crashed |= core.collides_with(target_hitbox),
+                }
+            }
+            if crashed {
+                contacts.push(Contact::Crash);
+            }
+        }
+
+        contacts
+    }
+}
+
+/// What destroyed the ship.
+#[derive(Clone, Copy)]
+pub enum DeathCause {
+    Enemy(EnemyKind),
+    Scenery,
+}
+
+/// Applies contacts to the world and emits what came of them. Returns what hit the ship, if
+/// anything, leaving it to the caller to decide whether that's fatal.
+pub fn damage(
+    world: &mut World,
+    contacts: Vec<Contact>,
+    events: &mut EventBus,
+) -> Option<DeathCause> {
+    let mut ship_hit = None;
+    // Shots go once every contact is handled, as one may hit several enemies in a tick
+    let mut spent = vec![];
+    for contact in contacts {
+        match contact {
+            Contact::Hit { projectile, target } => {
+                spent.push(projectile);
+                let Some(pos) = world
+                    .transforms
+                    .get(projectile)
+                    .map(|transform| transform.pos)
+                else {
+                    continue;
+                };
+                let Some(health) = world.healths.get_mut(target) else {
+                    continue;
+                };
+                health.0 -= 1;
+                events.emit(GameEvent::EnemyHit { pos });
+                if health.0 <= 0 {
+                    destroy(world, target, events);
+                }
+            }
+            Contact::ShipHit(enemy) => {
+                // Enemies shot down earlier this tick are gone already
+                if let Some(transform) = world.transforms.get(enemy) {
+                    let kind = EnemyKind::from_size(transform.size.x);
+                    ship_hit = Some(DeathCause::Enemy(kind));
+                }
+            }
+            Contact::Graze(enemy) => {
+                // Enemies shot down earlier this tick are gone already
+                if world.is_alive(enemy) && world.grazed.get(enemy).is_none() {
+                    world.grazed.insert(enemy, Grazed);
+                    events.emit(GameEvent::Grazed);
+                }
+            }
+            Contact::Collect(pickup) => {
+                if world.is_alive(pickup) {
+                    events.emit(GameEvent::PowerupCollected);
+                }
+                world.despawn(pickup);
+            }
+            Contact::Crash => ship_hit = Some(DeathCause::Scenery),
+        }
+    }
+    for projectile in spent {
+        world.despawn(projectile);
+    }
+    ship_hit
+}
+
+fn destroy(world: &mut World, entity: Entity, events: &mut EventBus) {
+    let Some(transform) = world.transforms.get(entity) else {
+        return;
+    };
+    let (pos, kind) = (transform.pos, EnemyKind::from_size(transform.size.x));
+    let points = world
+        .score_values
+        .get(entity)
+        .map_or(0, |value| value.points);
+    world.despawn(entity);
+
+    events.emit(GameEvent::EnemyKilled { pos, kind, points });
+}
+
+/// Draws sprites and particle emitters in z order, then hands back emitters that have finished.
+/// Particles aren't drawn at all while `delta_time` is zero. Otherwise they move on by the
+/// frame time, as emitters step themselves and can't be scaled.
+pub fn render(world: &mut World, resources: &Resources, sheets: &SpriteSheets, delta_time: f32) {
+    let mut order = world
+        .sprites
+        .iter()
+        .map(|(entity, sprite)| (sprite.z, entity, false))
+        .chain(
+            world
+                .emitters
+                .iter()
+                .map(|(entity, emitter)| (emitter.z, entity, true)),
+        )
+        .collect::<Vec<_>>();
+    order.sort_by_key(|(z, ..)| *z);
+
+    for (_, entity, is_emitter) in order {
+        let Some(transform) = world.transforms.get(entity) else {
+            continue;
+        };
+        if is_emitter {
+            // Emitters always step by the frame time when drawn, so a frozen simulation hides
+            // them rather than letting its particles drift
+            if delta_time == 0.0 {
+                continue;
+            }
+            if let Some(emitter) = world.emitters.get_mut(entity) {
+                emitter.emitter.draw(transform.pos);
+                // Its particles age by the frame time whatever the time scale, so this does too
+                if !emitter.emitter.config.emitting {
+                    emitter.stopped_for += get_frame_time();
+                }
+            }
+            continue;
+        }
+
+        let Some(sprite) = world.sprites.get(entity) else {
+            continue;
+        };
+        match sprite.kind {
+            SpriteKind::Sheet(sheet) => {
+                let frame = sheets.frame(sheet);
+                draw_texture_ex(
+                    sheet.texture(resources),
+                    transform.pos.x - transform.size.x / 2.0,
+                    transform.pos.y - transform.size.y / 2.0,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(transform.size),
+                        source: Some(frame.source_rect),
+                        ..Default::default()
+                    },
+                );
+            }
+            SpriteKind::Orb(color) => {
+                let radius = transform.size.x / 2.0;
+                draw_circle(transform.pos.x, transform.pos.y, radius, color);
+                draw_circle_lines(transform.pos.x, transform.pos.y, radius, 2.0, WHITE);
+            }
+        }
+    }
+
+    let finished = world
+        .emitters
+        .iter()
+        .filter(|(_, emitter)| emitter.is_finished())
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+    for entity in finished {
+        world.remove_emitter(entity);
+        if world.sprites.get(entity).is_none() {
+            world.despawn(entity);
+        }
+    }
+}
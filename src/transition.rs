@@ -0,0 +1,113 @@
+use macroquad::prelude::*;
+
+use crate::{canvas, scenes::Transition, shader};
+
+/// Time taken to cover the screen, and the same again to uncover it.
+const HALF_DURATION: f32 = 0.4;
+const DISSOLVE_BLOCK_SIZE: f32 = 8.0;
+
+#[derive(Clone, Copy)]
+pub enum Effect {
+    Fade,
+    Wipe,
+    Dissolve,
+}
+
+struct Active {
+    effect: Effect,
+    elapsed: f32,
+    /// Applied once the screen is fully covered.
+    pending: Option<Transition>,
+}
+
+/// Covers the screen, switches scenes while nothing is visible, then uncovers it again.
+pub struct ScreenFader {
+    dissolve_material: Material,
+    active: Option<Active>,
+}
+
+impl ScreenFader {
+    pub fn new() -> Result<Self, macroquad::Error> {
+        let dissolve_material = load_material(
+            ShaderSource::Glsl {
+                vertex: shader::VERTEX_SHADER,
+                fragment: shader::DISSOLVE_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![
+                    UniformDesc::new("progress", UniformType::Float1),
+                    UniformDesc::new("block_size", UniformType::Float1),
+                ],
+                ..Default::default()
+            },
+        )?;
+
+        Ok(ScreenFader {
+            dissolve_material,
+            active: None,
+        })
+    }
+
+    pub fn start(&mut self, effect: Effect, transition: Transition) {
+        self.active = Some(Active {
+            effect,
+            elapsed: 0.0,
+            pending: Some(transition),
+        });
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// How much of the screen is covered, from 0 to 1.
+    pub fn coverage(&self) -> f32 {
+        self.active.as_ref().map_or(0.0, |active| {
+            1.0 - (active.elapsed - HALF_DURATION).abs() / HALF_DURATION
+        })
+    }
+
+    /// Advances the effect. Returns the pending transition once the screen is fully covered.
+    pub fn update(&mut self, delta_time: f32) -> Option<Transition> {
+        let active = self.active.as_mut()?;
+        active.elapsed += delta_time;
+        if active.elapsed >= HALF_DURATION * 2.0 {
+            let pending = active.pending.take();
+            self.active = None;
+            return pending;
+        }
+        if active.elapsed >= HALF_DURATION {
+            return active.pending.take();
+        }
+        None
+    }
+
+    pub fn draw(&self) {
+        let Some(active) = &self.active else {
+            return;
+        };
+
+        let coverage = self.coverage();
+        let (w, h) = (canvas::WIDTH, canvas::HEIGHT);
+        match active.effect {
+            Effect::Fade => draw_rectangle(0.0, 0.0, w, h, Color::new(0.0, 0.0, 0.0, coverage)),
+            Effect::Wipe => {
+                // Sweep in from the left, then carry on and uncover from the left too
+                let x = if active.elapsed < HALF_DURATION {
+                    0.0
+                } else {
+                    w * (1.0 - coverage)
+                };
+                draw_rectangle(x, 0.0, w * coverage, h, BLACK);
+            }
+            Effect::Dissolve => {
+                self.dissolve_material.set_uniform("progress", coverage);
+                self.dissolve_material
+                    .set_uniform("block_size", DISSOLVE_BLOCK_SIZE);
+                gl_use_material(&self.dissolve_material);
+                draw_rectangle(0.0, 0.0, w, h, BLACK);
+                gl_use_default_material();
+            }
+        }
+    }
+}